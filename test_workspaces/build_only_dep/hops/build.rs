@@ -0,0 +1,3 @@
+fn main() {
+    println!("cargo:rustc-env=HOPS_GRAIN={}", barley::BARLEY);
+}
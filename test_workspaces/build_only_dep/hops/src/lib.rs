@@ -0,0 +1 @@
+pub const HOPS: &str = env!("HOPS_GRAIN");
@@ -0,0 +1 @@
+pub const MALT: &str = env!("MALT_GRAIN");
@@ -0,0 +1,8 @@
+#[cfg(not(feature = "std"))]
+pub const GRAIN: &str = barley::BARLEY;
+
+#[cfg(feature = "std")]
+pub const GRAIN: &str = "oat";
+
+#[cfg(feature = "bake")]
+pub const FLOUR: &str = wheat::WHEAT;
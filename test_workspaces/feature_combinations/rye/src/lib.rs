@@ -0,0 +1 @@
+pub const RYE: &str = "rye";
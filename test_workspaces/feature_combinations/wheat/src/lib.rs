@@ -0,0 +1 @@
+pub const WHEAT: &str = "wheat";
@@ -0,0 +1 @@
+pub const BARLEY: &str = "barley";
@@ -0,0 +1,4 @@
+pub const RYE: &str = "rye";
+
+#[cfg(feature = "extra")]
+pub const EXTRA: &str = "extra";
@@ -0,0 +1,12 @@
+pub fn grains() -> [&'static str; 2] {
+    return [barley::BARLEY, rye::RYE];
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        assert_eq!(super::grains(), [barley::BARLEY, rye::RYE]);
+        assert_eq!(rye::EXTRA, "extra");
+    }
+}
@@ -0,0 +1 @@
+use purple as _;
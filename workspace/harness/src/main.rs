@@ -32,8 +32,14 @@ enum TestStatus {
 struct Test {
     folder: Utf8PathBuf,
     test_status: TestStatus,
+    features: reves::FeatureSelection,
     expected_unused_dependencies: HashSet<ExpectedUnusedDependency>,
     expected_orphans: HashSet<ExpectedOrphanArtifact>,
+    /// With the `dep_kind` each should be.
+    expected_mislabeled_dependencies: HashSet<ExpectedUnusedDependency>,
+    expected_redundant_dependencies: HashSet<ExpectedUnusedDependency>,
+    /// Non-optional dependencies only used with a feature.
+    expected_optional_dependencies: HashSet<ExpectedUnusedDependency>,
 }
 
 fn package_id_to_name(pkg_id: &cargo_metadata::PackageId) -> &str {
@@ -63,6 +69,23 @@ fn equal_unused_deps(
     return false;
 }
 
+fn equal_mislabeled_deps(
+    real_mislabeled: &[(&UnusedDependency, DependencyKind)],
+    expected_mislabeled: &HashSet<ExpectedUnusedDependency>,
+) -> bool {
+    if real_mislabeled.len() == expected_mislabeled.len() {
+        for (real_mislabeled, dep_kind) in real_mislabeled.iter() {
+            let mut expected: ExpectedUnusedDependency = unused_dep_to_expected(real_mislabeled);
+            expected.dep_kind = *dep_kind;
+            if !expected_mislabeled.contains(&expected) {
+                return false;
+            }
+        }
+        return true;
+    }
+    return false;
+}
+
 fn orphan_artifact_to_expected(orphan: &OrphanArtifact) -> ExpectedOrphanArtifact {
     return ExpectedOrphanArtifact {
         crate_name: package_id_to_name(&orphan.crate_id).to_owned(),
@@ -92,26 +115,35 @@ fn main() {
         Test {
             folder: Utf8PathBuf::from("link_dep"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "lily".to_owned(),
                 dependency: "buttercup".to_owned(),
                 dep_kind: DependencyKind::Normal,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("link_dep_sometimes"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "lily".to_owned(),
                 dependency: "buttercup".to_owned(),
                 dep_kind: DependencyKind::Normal,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("simple_unused"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![
                 ExpectedUnusedDependency {
                     dependant: "magenta".to_owned(),
@@ -130,36 +162,52 @@ fn main() {
                 },
             ]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("simple_used"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::new(),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("doc_test_used"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "lamb".to_owned(),
                 dependency: "chick".to_owned(),
                 dep_kind: DependencyKind::Development,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("doc_test_ignore_used"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "lamb".to_owned(),
                 dependency: "chick".to_owned(),
                 dep_kind: DependencyKind::Development,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("doc_broken_link"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![
                 ExpectedUnusedDependency {
                     dependant: "lamb".to_owned(),
@@ -173,20 +221,28 @@ fn main() {
                 },
             ]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("doc_working_link"),
             test_status: TestStatus::Todo,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "lamb".to_owned(),
                 dependency: "chick".to_owned(),
                 dep_kind: DependencyKind::Development,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("rename_crates_unused"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::from_iter(vec![
                 ExpectedUnusedDependency {
                     dependant: "magenta".to_owned(),
@@ -205,20 +261,123 @@ fn main() {
                 },
             ]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
+        },
+        Test {
+            folder: Utf8PathBuf::from("optional_unused"),
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
+            expected_unused_dependencies: HashSet::from_iter(vec![
+                ExpectedUnusedDependency {
+                    dependant: "magenta".to_owned(),
+                    dependency: "fuchsia".to_owned(),
+                    dep_kind: DependencyKind::Normal,
+                },
+                ExpectedUnusedDependency {
+                    dependant: "magenta".to_owned(),
+                    dependency: "purple".to_owned(),
+                    dep_kind: DependencyKind::Normal,
+                },
+            ]),
+            expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
+        },
+        Test {
+            folder: Utf8PathBuf::from("workspace_unused"),
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
+            expected_unused_dependencies: HashSet::from_iter(vec![
+                ExpectedUnusedDependency {
+                    dependant: "magenta".to_owned(),
+                    dependency: "fuchsia".to_owned(),
+                    dep_kind: DependencyKind::Normal,
+                },
+                ExpectedUnusedDependency {
+                    dependant: "magenta".to_owned(),
+                    dependency: "purple".to_owned(),
+                    dep_kind: DependencyKind::Development,
+                },
+            ]),
+            expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("mislabeled_dev_dep"),
-            test_status: TestStatus::Todo,
-            expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
+            expected_unused_dependencies: HashSet::new(),
+            expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
                 dependant: "wheat".to_owned(),
                 dependency: "barley".to_owned(),
+                dep_kind: DependencyKind::Development,
+            }]),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
+        },
+        Test {
+            folder: Utf8PathBuf::from("build_only_dep"),
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
+            expected_unused_dependencies: HashSet::new(),
+            expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::from_iter(vec![
+                ExpectedUnusedDependency {
+                    dependant: "malt".to_owned(),
+                    dependency: "barley".to_owned(),
+                    dep_kind: DependencyKind::Build,
+                },
+                ExpectedUnusedDependency {
+                    dependant: "hops".to_owned(),
+                    dependency: "barley".to_owned(),
+                    dep_kind: DependencyKind::Build,
+                },
+            ]),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
+        },
+        Test {
+            folder: Utf8PathBuf::from("redundant_dev_dep"),
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
+            expected_unused_dependencies: HashSet::new(),
+            expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
+                dependant: "wheat".to_owned(),
+                dependency: "barley".to_owned(),
+                dep_kind: DependencyKind::Development,
+            }]),
+            expected_optional_dependencies: HashSet::new(),
+        },
+        Test {
+            folder: Utf8PathBuf::from("feature_combinations"),
+            test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::Powerset { depth: None },
+            expected_unused_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
+                dependant: "oat".to_owned(),
+                dependency: "rye".to_owned(),
                 dep_kind: DependencyKind::Normal,
             }]),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::from_iter(vec![ExpectedUnusedDependency {
+                dependant: "oat".to_owned(),
+                dependency: "wheat".to_owned(),
+                dep_kind: DependencyKind::Normal,
+            }]),
         },
         Test {
             folder: Utf8PathBuf::from("orphans"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::new(),
             expected_orphans: HashSet::from_iter(vec![
                 ExpectedOrphanArtifact {
@@ -252,12 +411,19 @@ fn main() {
                     crate_relative_path: Utf8PathBuf::from("examples/orphan_example.rs"),
                 },
             ]),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
         Test {
             folder: Utf8PathBuf::from("charges"),
             test_status: TestStatus::Passing,
+            features: reves::FeatureSelection::All,
             expected_unused_dependencies: HashSet::new(),
             expected_orphans: HashSet::new(),
+            expected_mislabeled_dependencies: HashSet::new(),
+            expected_redundant_dependencies: HashSet::new(),
+            expected_optional_dependencies: HashSet::new(),
         },
     ];
 
@@ -270,6 +436,7 @@ fn main() {
         if let Ok(lint_results) = reves::lint_dependencies(
             test_workspaces.join(test.folder.as_path()).as_path(),
             true,
+            &test.features,
             &reves::CargoArgs {
                 color: clap::ColorChoice::Auto,
                 frozen: false,
@@ -285,6 +452,24 @@ fn main() {
                 &lint_results.unused_dependencies,
                 &test.expected_unused_dependencies,
             ) || !equal_orphan_artifacts(&lint_results.orphans, &test.expected_orphans)
+                || !equal_mislabeled_deps(
+                    &lint_results.mislabeled_dependencies(),
+                    &test.expected_mislabeled_dependencies,
+                )
+                || !equal_unused_deps(
+                    &lint_results.redundant_dev_dependencies,
+                    &test.expected_redundant_dependencies,
+                )
+                || !equal_unused_deps(
+                    &lint_results
+                        .optional_candidates
+                        .iter()
+                        .map(|optional_candidate| {
+                            return optional_candidate.unused_dependency.clone();
+                        })
+                        .collect(),
+                    &test.expected_optional_dependencies,
+                )
             {
                 match test.test_status {
                     TestStatus::Passing => {
@@ -0,0 +1,523 @@
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+use camino::Utf8PathBuf;
+
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::OptionalCandidate;
+use crate::OrphanArtifact;
+use crate::OrphanArtifactKind;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+/// Bumped whenever the meaning of an entry changes, older baselines are then
+/// rejected rather than silently matching nothing.
+const BASELINE_VERSION: u32 = 1;
+
+/// A finding identified by names rather than `PackageId`s, which change with
+/// the version, and location of packages.
+#[derive(
+    Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+#[serde(tag = "finding", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum BaselineEntry {
+    UnusedDependency {
+        package: String,
+        dependency: String,
+        /// The manifest key of a renamed dependency, distinguishing several
+        /// versions of the same package.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rename: Option<String>,
+        dep_kind: DependencyKind,
+    },
+    OrphanArtifact {
+        package: String,
+        kind: OrphanArtifactKind,
+        /// Relative to the package.
+        path: Utf8PathBuf,
+    },
+    UnusedWorkspaceDependency {
+        dependency: String,
+    },
+    MislabeledDependency {
+        package: String,
+        dependency: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rename: Option<String>,
+    },
+    RedundantDependency {
+        package: String,
+        dependency: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rename: Option<String>,
+    },
+    /// Without the feature, which may be renamed.
+    OptionalDependency {
+        package: String,
+        dependency: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rename: Option<String>,
+    },
+}
+
+/// The manifest key of a renamed dependency.
+fn rename(dep: &UnusedDependency) -> Option<String> {
+    if dep.manifest_key != dep.dependency_name.name {
+        return Some(dep.manifest_key.clone());
+    }
+    return None;
+}
+
+impl BaselineEntry {
+    fn of_unused_dependency(
+        results: &DependencyLintResults,
+        unused_dep: &UnusedDependency,
+    ) -> BaselineEntry {
+        return BaselineEntry::UnusedDependency {
+            package: results.package_name(&unused_dep.dependant).to_owned(),
+            dependency: unused_dep.dependency_name.name.to_string(),
+            rename: rename(unused_dep),
+            dep_kind: unused_dep.dep_kind,
+        };
+    }
+
+    fn of_mislabeled_dependency(
+        results: &DependencyLintResults,
+        mismarked_dep: &UnusedDependency,
+    ) -> BaselineEntry {
+        return BaselineEntry::MislabeledDependency {
+            package: results.package_name(&mismarked_dep.dependant).to_owned(),
+            dependency: mismarked_dep.dependency_name.name.to_string(),
+            rename: rename(mismarked_dep),
+        };
+    }
+
+    fn of_redundant_dev_dependency(
+        results: &DependencyLintResults,
+        redundant_dep: &UnusedDependency,
+    ) -> BaselineEntry {
+        return BaselineEntry::RedundantDependency {
+            package: results.package_name(&redundant_dep.dependant).to_owned(),
+            dependency: redundant_dep.dependency_name.name.to_string(),
+            rename: rename(redundant_dep),
+        };
+    }
+
+    fn of_optional_candidate(
+        results: &DependencyLintResults,
+        optional_candidate: &OptionalCandidate,
+    ) -> BaselineEntry {
+        let unused_dep: &UnusedDependency = &optional_candidate.unused_dependency;
+        return BaselineEntry::OptionalDependency {
+            package: results.package_name(&unused_dep.dependant).to_owned(),
+            dependency: unused_dep.dependency_name.name.to_string(),
+            rename: rename(unused_dep),
+        };
+    }
+
+    fn of_unused_workspace_dependency(
+        unused_workspace_dep: &UnusedWorkspaceDependency,
+    ) -> BaselineEntry {
+        return BaselineEntry::UnusedWorkspaceDependency {
+            dependency: unused_workspace_dep.key.clone(),
+        };
+    }
+
+    fn of_orphan(results: &DependencyLintResults, orphan: &OrphanArtifact) -> BaselineEntry {
+        return BaselineEntry::OrphanArtifact {
+            package: results.package_name(&orphan.crate_id).to_owned(),
+            kind: orphan.kind.clone(),
+            path: orphan.crate_relative_path.clone(),
+        };
+    }
+}
+
+impl std::fmt::Display for BaselineEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            BaselineEntry::UnusedDependency {
+                package,
+                dependency,
+                rename: Some(rename),
+                dep_kind,
+            } => write!(
+                f,
+                "unused {} `{}` (`{}`) of `{}`",
+                dep_kind, rename, dependency, package
+            ),
+            BaselineEntry::UnusedDependency {
+                package,
+                dependency,
+                rename: None,
+                dep_kind,
+            } => write!(f, "unused {} `{}` of `{}`", dep_kind, dependency, package),
+            BaselineEntry::OrphanArtifact {
+                package,
+                kind: _,
+                path,
+            } => write!(f, "orphan artifact `{}` of `{}`", path, package),
+            BaselineEntry::UnusedWorkspaceDependency { dependency } => {
+                write!(f, "unused workspace dependency `{}`", dependency)
+            }
+            BaselineEntry::MislabeledDependency {
+                package,
+                dependency,
+                rename: Some(rename),
+            } => write!(
+                f,
+                "mislabeled dependency `{}` (`{}`) of `{}`",
+                rename, dependency, package
+            ),
+            BaselineEntry::MislabeledDependency {
+                package,
+                dependency,
+                rename: None,
+            } => write!(f, "mislabeled dependency `{}` of `{}`", dependency, package),
+            BaselineEntry::RedundantDependency {
+                package,
+                dependency,
+                rename: Some(rename),
+            } => write!(
+                f,
+                "redundant dev-dependency `{}` (`{}`) of `{}`",
+                rename, dependency, package
+            ),
+            BaselineEntry::RedundantDependency {
+                package,
+                dependency,
+                rename: None,
+            } => write!(
+                f,
+                "redundant dev-dependency `{}` of `{}`",
+                dependency, package
+            ),
+            BaselineEntry::OptionalDependency {
+                package,
+                dependency,
+                rename: Some(rename),
+            } => write!(
+                f,
+                "dependency `{}` (`{}`) of `{}` which could be optional",
+                rename, dependency, package
+            ),
+            BaselineEntry::OptionalDependency {
+                package,
+                dependency,
+                rename: None,
+            } => write!(
+                f,
+                "dependency `{}` of `{}` which could be optional",
+                dependency, package
+            ),
+        };
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+struct Baseline {
+    version: u32,
+    entries: BTreeSet<BaselineEntry>,
+}
+
+/// The entries for all findings in `results`.
+pub(crate) fn baseline_entries(results: &DependencyLintResults) -> BTreeSet<BaselineEntry> {
+    let mut entries = BTreeSet::<BaselineEntry>::new();
+    for unused_dep in results.unused_dependencies.iter() {
+        entries.insert(BaselineEntry::of_unused_dependency(results, unused_dep));
+    }
+    for orphan in results.orphans.iter() {
+        entries.insert(BaselineEntry::of_orphan(results, orphan));
+    }
+    for unused_workspace_dep in results.unused_workspace_dependencies.iter() {
+        entries.insert(BaselineEntry::of_unused_workspace_dependency(
+            unused_workspace_dep,
+        ));
+    }
+    for (mismarked_dep, _) in results.mislabeled_dependencies().into_iter() {
+        entries.insert(BaselineEntry::of_mislabeled_dependency(
+            results,
+            mismarked_dep,
+        ));
+    }
+    for redundant_dep in results.redundant_dev_dependencies.iter() {
+        entries.insert(BaselineEntry::of_redundant_dev_dependency(
+            results,
+            redundant_dep,
+        ));
+    }
+    for optional_candidate in results.optional_candidates.iter() {
+        entries.insert(BaselineEntry::of_optional_candidate(
+            results,
+            optional_candidate,
+        ));
+    }
+    return entries;
+}
+
+pub(crate) fn write_baseline(path: &Path, entries: &BTreeSet<BaselineEntry>) -> anyhow::Result<()> {
+    let mut baseline_text: String = serde_json::to_string_pretty(&Baseline {
+        version: BASELINE_VERSION,
+        entries: entries.clone(),
+    })?;
+    baseline_text.push('\n');
+    std::fs::write(path, baseline_text)
+        .with_context(|| format!("Failed to write baseline {}", path.display()))?;
+    return Ok(());
+}
+
+pub(crate) fn read_baseline(path: &Path) -> anyhow::Result<BTreeSet<BaselineEntry>> {
+    let baseline_text: String = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+    let baseline: Baseline = serde_json::from_str(&baseline_text)
+        .with_context(|| format!("Invalid baseline {}", path.display()))?;
+    anyhow::ensure!(
+        baseline.version == BASELINE_VERSION,
+        "Unsupported baseline version {} in {}, expected {}",
+        baseline.version,
+        path.display(),
+        BASELINE_VERSION
+    );
+    return Ok(baseline.entries);
+}
+
+/// Removes findings which are in `baseline`, returning the baseline entries
+/// which no longer match a finding (as they have since been fixed).
+pub(crate) fn remove_baselined_findings(
+    results: &mut DependencyLintResults,
+    baseline: &BTreeSet<BaselineEntry>,
+) -> Vec<BaselineEntry> {
+    let mut matched = BTreeSet::<BaselineEntry>::new();
+
+    let mut unused_dependencies = HashSet::<UnusedDependency>::new();
+    for unused_dep in results.unused_dependencies.iter() {
+        let entry = BaselineEntry::of_unused_dependency(results, unused_dep);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            unused_dependencies.insert(unused_dep.clone());
+        }
+    }
+    let mut orphans = HashSet::<OrphanArtifact>::new();
+    for orphan in results.orphans.iter() {
+        let entry = BaselineEntry::of_orphan(results, orphan);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            orphans.insert(orphan.clone());
+        }
+    }
+    let mut unused_workspace_dependencies = HashSet::<UnusedWorkspaceDependency>::new();
+    for unused_workspace_dep in results.unused_workspace_dependencies.iter() {
+        let entry = BaselineEntry::of_unused_workspace_dependency(unused_workspace_dep);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            unused_workspace_dependencies.insert(unused_workspace_dep.clone());
+        }
+    }
+    let mut mismarked_dev_dependencies = HashSet::<UnusedDependency>::new();
+    for mismarked_dep in results.mismarked_dev_dependencies.iter() {
+        let entry = BaselineEntry::of_mislabeled_dependency(results, mismarked_dep);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            mismarked_dev_dependencies.insert(mismarked_dep.clone());
+        }
+    }
+    let mut mismarked_build_dependencies = HashSet::<UnusedDependency>::new();
+    for mismarked_dep in results.mismarked_build_dependencies.iter() {
+        let entry = BaselineEntry::of_mislabeled_dependency(results, mismarked_dep);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            mismarked_build_dependencies.insert(mismarked_dep.clone());
+        }
+    }
+    results.unused_dependencies = unused_dependencies;
+    results.orphans = orphans;
+    results.unused_workspace_dependencies = unused_workspace_dependencies;
+    let mut redundant_dev_dependencies = HashSet::<UnusedDependency>::new();
+    for redundant_dep in results.redundant_dev_dependencies.iter() {
+        let entry = BaselineEntry::of_redundant_dev_dependency(results, redundant_dep);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            redundant_dev_dependencies.insert(redundant_dep.clone());
+        }
+    }
+    results.mismarked_dev_dependencies = mismarked_dev_dependencies;
+    results.mismarked_build_dependencies = mismarked_build_dependencies;
+    let mut optional_candidates = HashSet::<OptionalCandidate>::new();
+    for optional_candidate in results.optional_candidates.iter() {
+        let entry = BaselineEntry::of_optional_candidate(results, optional_candidate);
+        if baseline.contains(&entry) {
+            matched.insert(entry);
+        } else {
+            optional_candidates.insert(optional_candidate.clone());
+        }
+    }
+    results.redundant_dev_dependencies = redundant_dev_dependencies;
+    results.optional_candidates = optional_candidates;
+
+    return baseline.difference(&matched).cloned().collect();
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use camino::Utf8PathBuf;
+
+    use super::BaselineEntry;
+    use crate::test_util;
+    use crate::DependencyKind;
+    use crate::OrphanArtifactKind;
+    use crate::UnusedDependency;
+
+    #[test]
+    fn test_baseline_entries() {
+        let entries: BTreeSet<BaselineEntry> =
+            super::baseline_entries(&test_util::sample_results());
+        assert_eq!(
+            serde_json::to_value(&entries).unwrap(),
+            serde_json::json!([
+                {
+                    "finding": "unused-dependency",
+                    "package": "magenta",
+                    "dependency": "fuchsia",
+                    "dep_kind": "normal",
+                },
+                {
+                    "finding": "unused-dependency",
+                    "package": "magenta",
+                    "dependency": "fuchsia",
+                    "dep_kind": "build",
+                },
+                {
+                    "finding": "unused-dependency",
+                    "package": "magenta",
+                    "dependency": "purple",
+                    "dep_kind": "dev",
+                },
+                {
+                    "finding": "orphan-artifact",
+                    "package": "magenta",
+                    "kind": "binary",
+                    "path": "src/bin/orphan_bin.rs",
+                },
+                {
+                    "finding": "unused-workspace-dependency",
+                    "dependency": "lapin",
+                },
+                {
+                    "finding": "mislabeled-dependency",
+                    "package": "magenta",
+                    "dependency": "barley",
+                },
+                {
+                    "finding": "optional-dependency",
+                    "package": "magenta",
+                    "dependency": "violet",
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_renamed_baseline_entry() {
+        let mut results = test_util::sample_results();
+        let mut renamed: UnusedDependency =
+            test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Normal);
+        renamed.manifest_key = "fuchsia07".to_owned();
+        results.unused_dependencies.insert(renamed);
+
+        let entries: BTreeSet<BaselineEntry> = super::baseline_entries(&results);
+        assert_eq!(entries.len(), 8);
+        let renamed_entry = BaselineEntry::UnusedDependency {
+            package: "magenta".to_owned(),
+            dependency: "fuchsia".to_owned(),
+            rename: Some("fuchsia07".to_owned()),
+            dep_kind: DependencyKind::Normal,
+        };
+        assert!(entries.contains(&renamed_entry));
+        assert_eq!(
+            renamed_entry.to_string(),
+            "unused dependency `fuchsia07` (`fuchsia`) of `magenta`"
+        );
+    }
+
+    #[test]
+    fn test_remove_baselined_findings() {
+        let mut results = test_util::sample_results();
+        results
+            .redundant_dev_dependencies
+            .insert(test_util::unused_dependency(
+                "magenta",
+                "fuchsia",
+                DependencyKind::Development,
+            ));
+        let fixed = BaselineEntry::UnusedDependency {
+            package: "purple".to_owned(),
+            dependency: "fuchsia".to_owned(),
+            rename: None,
+            dep_kind: DependencyKind::Normal,
+        };
+        let baseline = BTreeSet::<BaselineEntry>::from([
+            BaselineEntry::UnusedDependency {
+                package: "magenta".to_owned(),
+                dependency: "fuchsia".to_owned(),
+                rename: None,
+                dep_kind: DependencyKind::Normal,
+            },
+            BaselineEntry::UnusedDependency {
+                package: "magenta".to_owned(),
+                dependency: "purple".to_owned(),
+                rename: None,
+                dep_kind: DependencyKind::Development,
+            },
+            BaselineEntry::OrphanArtifact {
+                package: "magenta".to_owned(),
+                kind: OrphanArtifactKind::Binary,
+                path: Utf8PathBuf::from("src/bin/orphan_bin.rs"),
+            },
+            BaselineEntry::UnusedWorkspaceDependency {
+                dependency: "lapin".to_owned(),
+            },
+            BaselineEntry::MislabeledDependency {
+                package: "magenta".to_owned(),
+                dependency: "barley".to_owned(),
+                rename: None,
+            },
+            BaselineEntry::RedundantDependency {
+                package: "magenta".to_owned(),
+                dependency: "fuchsia".to_owned(),
+                rename: None,
+            },
+            BaselineEntry::OptionalDependency {
+                package: "magenta".to_owned(),
+                dependency: "violet".to_owned(),
+                rename: None,
+            },
+            fixed.clone(),
+        ]);
+
+        assert_eq!(
+            super::remove_baselined_findings(&mut results, &baseline),
+            vec![fixed]
+        );
+        assert!(results.orphans.is_empty());
+        assert!(results.unused_workspace_dependencies.is_empty());
+        assert!(results.mismarked_dev_dependencies.is_empty());
+        assert!(results.redundant_dev_dependencies.is_empty());
+        assert!(results.optional_candidates.is_empty());
+        assert_eq!(results.unused_dependencies.len(), 1);
+        assert_eq!(
+            results.unused_dependencies.iter().next().unwrap().dep_kind,
+            DependencyKind::Build
+        );
+    }
+}
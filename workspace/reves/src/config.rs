@@ -0,0 +1,366 @@
+use anyhow::Context;
+
+use crate::DependencyKind;
+use crate::LintCategory;
+use crate::UnusedDependency;
+
+/*
+  Configuration is read from the `reves` table of `[workspace.metadata]` and
+  `[package.metadata]`, for example
+
+    [workspace.metadata.reves]
+    unused-dev = "warn"
+    orphans = "allow"
+    unused-workspace = "warn"
+    mislabeled = "deny"
+    redundant = "allow"
+
+  with `[package.metadata.reves]` overriding the workspace settings for that
+  package (other than `unused-workspace`, which isn't specific to a package).
+  The unused dependency, and orphan categories are denied by default, while
+  `mislabeled`, `redundant`, and `optional` are only suggestions, so they are
+  warned about by default.
+  Packages may also ignore deliberately unused dependencies
+
+    [package.metadata.reves]
+    ignored = [
+      { name = "backtrace", kind = "normal", reason = "Forces a minimum version" },
+    ]
+*/
+
+/// How findings of a category are treated.
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    /// Reported, but doesn't fail the run.
+    Warn,
+    /// Reported, and fails the run.
+    Deny,
+}
+
+impl Level {
+    /// The severity used when reporting a finding at this level, as named by
+    /// SARIF, rustc, and GitHub annotations.
+    pub(crate) fn report_level(self) -> &'static str {
+        return match self {
+            Level::Deny => "error",
+            Level::Warn | Level::Allow => "warning",
+        };
+    }
+}
+
+/// A deliberately unused dependency, which isn't reported.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct IgnoredDependency {
+    /// The name of the dependency's package.
+    pub name: String,
+    /// Matches every kind if `None`.
+    pub kind: Option<DependencyKind>,
+    /// Why the dependency is unused, required so ignores can be reviewed.
+    pub reason: String,
+}
+
+impl IgnoredDependency {
+    pub(crate) fn matches(&self, unused_dep: &UnusedDependency) -> bool {
+        if unused_dep.dependency_name.name != self.name {
+            return false;
+        }
+        return match self.kind {
+            Some(kind) => kind == unused_dep.dep_kind,
+            None => true,
+        };
+    }
+}
+
+/// A `reves` metadata table, settings which aren't specified are `None`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Config {
+    unused_normal: Option<Level>,
+    unused_dev: Option<Level>,
+    unused_build: Option<Level>,
+    orphans: Option<Level>,
+    /// Only allowed in `[workspace.metadata.reves]`.
+    unused_workspace: Option<Level>,
+    mislabeled: Option<Level>,
+    redundant: Option<Level>,
+    optional: Option<Level>,
+    #[serde(default)]
+    pub(crate) ignored: Vec<IgnoredDependency>,
+}
+
+impl Config {
+    /// `metadata` is the `metadata` field of a package, or the
+    /// `workspace_metadata` field of `cargo metadata`.
+    pub(crate) fn from_metadata(metadata: &serde_json::Value) -> anyhow::Result<Config> {
+        let config: Config = match metadata.get("reves") {
+            Some(config) => {
+                serde_json::from_value(config.clone()).context("Invalid reves metadata")?
+            }
+            None => Config::default(),
+        };
+        for ignored in config.ignored.iter() {
+            anyhow::ensure!(
+                !ignored.reason.trim().is_empty(),
+                "The ignored dependency `{}` must have a non-empty reason",
+                ignored.name
+            );
+        }
+        return Ok(config);
+    }
+
+    pub(crate) fn level(&self, category: LintCategory) -> Option<Level> {
+        return match category {
+            LintCategory::UnusedNormal => self.unused_normal,
+            LintCategory::UnusedDev => self.unused_dev,
+            LintCategory::UnusedBuild => self.unused_build,
+            LintCategory::Orphans => self.orphans,
+            LintCategory::UnusedWorkspace => self.unused_workspace,
+            LintCategory::Mislabeled => self.mislabeled,
+            LintCategory::Redundant => self.redundant,
+            LintCategory::Optional => self.optional,
+        };
+    }
+}
+
+/// The level of every category for a package.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Levels {
+    pub unused_normal: Level,
+    pub unused_dev: Level,
+    pub unused_build: Level,
+    pub orphans: Level,
+    pub unused_workspace: Level,
+    pub mislabeled: Level,
+    pub redundant: Level,
+    pub optional: Level,
+}
+
+impl Default for Levels {
+    fn default() -> Levels {
+        return Levels {
+            unused_normal: Level::Deny,
+            unused_dev: Level::Deny,
+            unused_build: Level::Deny,
+            orphans: Level::Deny,
+            unused_workspace: Level::Deny,
+            mislabeled: Level::Warn,
+            redundant: Level::Warn,
+            optional: Level::Warn,
+        };
+    }
+}
+
+impl Levels {
+    /// Package settings take precedence over workspace settings, which take
+    /// precedence over the defaults (`deny`, or `warn` for suggestions).
+    pub(crate) fn resolve(workspace: &Config, package: &Config) -> Levels {
+        let mut levels = Levels::default();
+        for category in LintCategory::ALL.iter() {
+            if let Some(level) = package.level(*category).or(workspace.level(*category)) {
+                *levels.level_mut(*category) = level;
+            }
+        }
+        return levels;
+    }
+
+    pub fn level(&self, category: LintCategory) -> Level {
+        return match category {
+            LintCategory::UnusedNormal => self.unused_normal,
+            LintCategory::UnusedDev => self.unused_dev,
+            LintCategory::UnusedBuild => self.unused_build,
+            LintCategory::Orphans => self.orphans,
+            LintCategory::UnusedWorkspace => self.unused_workspace,
+            LintCategory::Mislabeled => self.mislabeled,
+            LintCategory::Redundant => self.redundant,
+            LintCategory::Optional => self.optional,
+        };
+    }
+
+    pub fn level_mut(&mut self, category: LintCategory) -> &mut Level {
+        return match category {
+            LintCategory::UnusedNormal => &mut self.unused_normal,
+            LintCategory::UnusedDev => &mut self.unused_dev,
+            LintCategory::UnusedBuild => &mut self.unused_build,
+            LintCategory::Orphans => &mut self.orphans,
+            LintCategory::UnusedWorkspace => &mut self.unused_workspace,
+            LintCategory::Mislabeled => &mut self.mislabeled,
+            LintCategory::Redundant => &mut self.redundant,
+            LintCategory::Optional => &mut self.optional,
+        };
+    }
+
+    /// `--deny` replaces which categories fail the run: the listed categories
+    /// are denied, and the others are at most warned about.
+    pub(crate) fn override_deny(&mut self, deny: &[LintCategory]) {
+        for category in LintCategory::ALL.iter() {
+            let level: &mut Level = self.level_mut(*category);
+            if deny.contains(category) {
+                *level = Level::Deny;
+            } else {
+                *level = std::cmp::min(*level, Level::Warn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Config;
+    use super::IgnoredDependency;
+    use super::Level;
+    use super::Levels;
+    use crate::test_util;
+    use crate::DependencyKind;
+    use crate::LintCategory;
+
+    #[test]
+    fn test_from_metadata() {
+        assert_eq!(
+            Config::from_metadata(&serde_json::Value::Null).unwrap(),
+            Config::default()
+        );
+        assert_eq!(
+            Config::from_metadata(&serde_json::json!({"other-tool": {"key": 1}})).unwrap(),
+            Config::default()
+        );
+        assert_eq!(
+            Config::from_metadata(&serde_json::json!({
+                "reves": {"unused-dev": "warn", "orphans": "allow"}
+            }))
+            .unwrap(),
+            Config {
+                unused_normal: None,
+                unused_dev: Some(Level::Warn),
+                unused_build: None,
+                orphans: Some(Level::Allow),
+                unused_workspace: None,
+                mislabeled: None,
+                redundant: None,
+                optional: None,
+                ignored: Vec::new(),
+            }
+        );
+        assert!(
+            Config::from_metadata(&serde_json::json!({"reves": {"unused-dev": "error"}})).is_err()
+        );
+        assert!(Config::from_metadata(&serde_json::json!({"reves": {"unused": "warn"}})).is_err());
+    }
+
+    #[test]
+    fn test_ignored() {
+        let config: Config = Config::from_metadata(&serde_json::json!({
+            "reves": {"ignored": [
+                {"name": "backtrace", "kind": "normal", "reason": "Forces a minimum version"},
+                {"name": "fuchsia", "reason": "Used by macros"},
+            ]}
+        }))
+        .unwrap();
+        assert_eq!(
+            config.ignored,
+            vec![
+                IgnoredDependency {
+                    name: "backtrace".to_owned(),
+                    kind: Some(DependencyKind::Normal),
+                    reason: "Forces a minimum version".to_owned(),
+                },
+                IgnoredDependency {
+                    name: "fuchsia".to_owned(),
+                    kind: None,
+                    reason: "Used by macros".to_owned(),
+                },
+            ]
+        );
+
+        let fuchsia = test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Build);
+        assert!(!config.ignored[0].matches(&fuchsia));
+        assert!(config.ignored[1].matches(&fuchsia));
+
+        // The names written by `reves --fix=interactive` are accepted.
+        for kind in [
+            DependencyKind::Normal,
+            DependencyKind::Development,
+            DependencyKind::Build,
+        ] {
+            let config: Config = Config::from_metadata(&serde_json::json!({
+                "reves": {"ignored": [
+                    {"name": "fuchsia", "kind": kind.serde_name(), "reason": "Used by macros"},
+                ]}
+            }))
+            .unwrap();
+            assert_eq!(config.ignored[0].kind, Some(kind));
+        }
+
+        assert!(Config::from_metadata(&serde_json::json!({
+            "reves": {"ignored": [{"name": "backtrace", "kind": "normal"}]}
+        }))
+        .is_err());
+        assert!(Config::from_metadata(&serde_json::json!({
+            "reves": {"ignored": [{"name": "backtrace", "reason": " "}]}
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let workspace: Config = Config::from_metadata(&serde_json::json!({
+            "reves": {"unused-dev": "warn", "unused-build": "warn", "orphans": "allow"}
+        }))
+        .unwrap();
+        let package: Config = Config::from_metadata(&serde_json::json!({
+            "reves": {"unused-dev": "deny"}
+        }))
+        .unwrap();
+        assert_eq!(
+            Levels::resolve(&workspace, &package),
+            Levels {
+                unused_normal: Level::Deny,
+                unused_dev: Level::Deny,
+                unused_build: Level::Warn,
+                orphans: Level::Allow,
+                unused_workspace: Level::Deny,
+                mislabeled: Level::Warn,
+                redundant: Level::Warn,
+                optional: Level::Warn,
+            }
+        );
+        assert_eq!(
+            Levels::resolve(&Config::default(), &Config::default()),
+            Levels::default()
+        );
+    }
+
+    #[test]
+    fn test_override_deny() {
+        let mut levels = Levels {
+            unused_normal: Level::Deny,
+            unused_dev: Level::Warn,
+            unused_build: Level::Allow,
+            orphans: Level::Allow,
+            unused_workspace: Level::Warn,
+            mislabeled: Level::Warn,
+            redundant: Level::Deny,
+            optional: Level::Allow,
+        };
+        levels.override_deny(&[LintCategory::UnusedDev, LintCategory::Orphans]);
+        assert_eq!(
+            levels,
+            Levels {
+                unused_normal: Level::Warn,
+                unused_dev: Level::Deny,
+                unused_build: Level::Allow,
+                orphans: Level::Deny,
+                unused_workspace: Level::Warn,
+                mislabeled: Level::Warn,
+                redundant: Level::Warn,
+                optional: Level::Allow,
+            }
+        );
+    }
+}
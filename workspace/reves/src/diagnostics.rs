@@ -0,0 +1,677 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::manifest;
+use crate::manifest::ManifestEdit;
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::ManifestSpan;
+use crate::OptionalCandidate;
+use crate::UnusedDependency;
+
+/*
+  Mirrors the `cargo --message-format=json` messages (as parsed by
+  `cargo_metadata::Message`) so editors which already understand `cargo`
+  diagnostics (such as rust-analyzer's flycheck) can show unused dependencies
+  directly in Cargo.toml.
+*/
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum Message<'a> {
+    CompilerMessage {
+        package_id: &'a cargo_metadata::PackageId,
+        manifest_path: &'a Utf8Path,
+        target: Target<'a>,
+        message: Box<Diagnostic>,
+    },
+    BuildFinished {
+        success: bool,
+    },
+}
+
+/// There is no real artifact for a manifest, so the manifest itself is used.
+#[derive(Debug, serde::Serialize)]
+struct Target<'a> {
+    name: &'a str,
+    kind: [&'static str; 1],
+    crate_types: [&'static str; 0],
+    src_path: &'a Utf8Path,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Diagnostic {
+    message: String,
+    code: Option<DiagnosticCode>,
+    level: &'static str,
+    spans: Vec<DiagnosticSpan>,
+    children: Vec<Diagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DiagnosticCode {
+    code: &'static str,
+    explanation: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DiagnosticSpan {
+    file_name: Utf8PathBuf,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    text: Vec<DiagnosticSpanLine>,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<&'static str>,
+    expansion: Option<()>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DiagnosticSpanLine {
+    text: String,
+    highlight_start: usize,
+    highlight_end: usize,
+}
+
+fn span_lines(manifest_text: &str, span: &ManifestSpan) -> Vec<DiagnosticSpanLine> {
+    let mut lines = Vec::<DiagnosticSpanLine>::new();
+    for (i, line) in manifest_text.lines().enumerate() {
+        let line_number: usize = i + 1;
+        if line_number < span.line_start || line_number > span.line_end {
+            continue;
+        }
+        let line_len: usize = line.chars().count();
+        lines.push(DiagnosticSpanLine {
+            text: line.to_owned(),
+            highlight_start: if line_number == span.line_start {
+                span.column_start
+            } else {
+                1
+            },
+            highlight_end: if line_number == span.line_end {
+                span.column_end
+            } else {
+                line_len + 1
+            },
+        });
+    }
+    return lines;
+}
+
+fn diagnostic_span(
+    manifest_path: &Utf8Path,
+    manifest_text: &str,
+    span: &ManifestSpan,
+    is_primary: bool,
+) -> DiagnosticSpan {
+    return DiagnosticSpan {
+        file_name: manifest_path.to_owned(),
+        byte_start: span.byte_start,
+        byte_end: span.byte_end,
+        line_start: span.line_start,
+        line_end: span.line_end,
+        column_start: span.column_start,
+        column_end: span.column_end,
+        is_primary,
+        text: span_lines(manifest_text, span),
+        label: None,
+        suggested_replacement: None,
+        suggestion_applicability: None,
+        expansion: None,
+    };
+}
+
+/// Roughly follows the `rustc` human readable format.
+fn render(
+    level: &str,
+    message: &str,
+    manifest_path: &Utf8Path,
+    manifest_text: &str,
+    span: Option<&ManifestSpan>,
+    help: &str,
+) -> String {
+    let mut rendered: String = format!("{}: {}\n", level, message);
+    match span {
+        Some(span) => {
+            let gutter_width: usize = span.line_start.to_string().len();
+            let gutter: String = " ".repeat(gutter_width);
+            let line: &str = manifest_text.lines().nth(span.line_start - 1).unwrap_or("");
+            let highlight_len: usize = if span.line_start == span.line_end {
+                span.column_end - span.column_start
+            } else {
+                line.chars().count() + 1 - span.column_start
+            };
+            rendered.push_str(&format!(
+                "{}--> {}:{}:{}\n",
+                gutter, manifest_path, span.line_start, span.column_start
+            ));
+            rendered.push_str(&format!("{} |\n", gutter));
+            rendered.push_str(&format!("{} | {}\n", span.line_start, line));
+            rendered.push_str(&format!(
+                "{} | {}{}\n",
+                gutter,
+                " ".repeat(span.column_start - 1),
+                "^".repeat(std::cmp::max(highlight_len, 1))
+            ));
+            rendered.push_str(&format!("{} |\n", gutter));
+            rendered.push_str(&format!("{} = help: {}\n", gutter, help));
+        }
+        None => {
+            rendered.push_str(&format!(" --> {}\n", manifest_path));
+            rendered.push_str(&format!("  = help: {}\n", help));
+        }
+    }
+    rendered.push('\n');
+    return rendered;
+}
+
+fn unused_dependency_message<'a>(
+    results: &'a DependencyLintResults,
+    unused_dep: &'a UnusedDependency,
+    manifest_text: &str,
+) -> anyhow::Result<Message<'a>> {
+    let manifest_path: &Utf8Path = unused_dep.dependant_manifest_path.as_path();
+    let message: String = format!(
+        "unused {} `{}`",
+        unused_dep.dep_kind, unused_dep.dependency_name.name
+    );
+    let help: String = format!("remove the {}", unused_dep.dep_kind);
+    let level: &'static str = results.unused_dependency_level(unused_dep).report_level();
+
+    let mut spans = Vec::<DiagnosticSpan>::new();
+    if let Some(span) = unused_dep.manifest_span.as_ref() {
+        spans.push(diagnostic_span(manifest_path, manifest_text, span, true));
+    }
+
+    let removal: Option<ManifestEdit> = manifest::dependency_removal(
+        manifest_text,
+        unused_dep.dep_kind,
+        unused_dep.targets.as_slice(),
+        unused_dep.manifest_key.as_str(),
+    )?;
+    let mut help_spans = Vec::<DiagnosticSpan>::new();
+    if let Some(removal) = removal {
+        let mut help_span: DiagnosticSpan =
+            diagnostic_span(manifest_path, manifest_text, &removal.span, true);
+        help_span.suggested_replacement = Some(removal.replacement);
+        help_span.suggestion_applicability = Some("MachineApplicable");
+        help_spans.push(help_span);
+    }
+
+    return Ok(Message::CompilerMessage {
+        package_id: &unused_dep.dependant,
+        manifest_path,
+        target: Target {
+            name: results.package_name(&unused_dep.dependant),
+            kind: ["manifest"],
+            crate_types: [],
+            src_path: manifest_path,
+        },
+        message: Box::new(Diagnostic {
+            rendered: Some(render(
+                level,
+                message.as_str(),
+                manifest_path,
+                manifest_text,
+                unused_dep.manifest_span.as_ref(),
+                help.as_str(),
+            )),
+            message,
+            code: Some(DiagnosticCode {
+                code: unused_dep.dep_kind.unused_finding_id(),
+                explanation: None,
+            }),
+            level,
+            spans,
+            children: vec![Diagnostic {
+                message: help,
+                code: None,
+                level: "help",
+                spans: help_spans,
+                children: Vec::new(),
+                rendered: None,
+            }],
+        }),
+    });
+}
+
+/// `dep_kind` is the kind of dependency `mismarked_dep` should be.
+fn mislabeled_dependency_message<'a>(
+    results: &'a DependencyLintResults,
+    mismarked_dep: &'a UnusedDependency,
+    dep_kind: DependencyKind,
+    manifest_text: &str,
+) -> Message<'a> {
+    let manifest_path: &Utf8Path = mismarked_dep.dependant_manifest_path.as_path();
+    let users: &str = match dep_kind {
+        DependencyKind::Build => "the build script",
+        DependencyKind::Normal | DependencyKind::Development => "tests, benches, or examples",
+    };
+    let message: String = format!(
+        "dependency `{}` is only used by {}",
+        mismarked_dep.dependency_name.name, users
+    );
+    // Moving the entry isn't a single replacement, so there is no suggestion.
+    let help: String = format!("move it to [{}]", manifest::dep_kind_to_toml_key(dep_kind));
+    let level: &'static str = results
+        .mislabeled_dependency_level(mismarked_dep)
+        .report_level();
+
+    let mut spans = Vec::<DiagnosticSpan>::new();
+    if let Some(span) = mismarked_dep.manifest_span.as_ref() {
+        spans.push(diagnostic_span(manifest_path, manifest_text, span, true));
+    }
+
+    return Message::CompilerMessage {
+        package_id: &mismarked_dep.dependant,
+        manifest_path,
+        target: Target {
+            name: results.package_name(&mismarked_dep.dependant),
+            kind: ["manifest"],
+            crate_types: [],
+            src_path: manifest_path,
+        },
+        message: Box::new(Diagnostic {
+            rendered: Some(render(
+                level,
+                message.as_str(),
+                manifest_path,
+                manifest_text,
+                mismarked_dep.manifest_span.as_ref(),
+                help.as_str(),
+            )),
+            message,
+            code: Some(DiagnosticCode {
+                code: "mislabeled",
+                explanation: None,
+            }),
+            level,
+            spans,
+            children: vec![Diagnostic {
+                message: help,
+                code: None,
+                level: "help",
+                spans: Vec::new(),
+                children: Vec::new(),
+                rendered: None,
+            }],
+        }),
+    };
+}
+
+fn redundant_dev_dependency_message<'a>(
+    results: &'a DependencyLintResults,
+    redundant_dep: &'a UnusedDependency,
+    manifest_text: &str,
+) -> anyhow::Result<Message<'a>> {
+    let manifest_path: &Utf8Path = redundant_dep.dependant_manifest_path.as_path();
+    let message: String = format!(
+        "dev-dependency `{}` is redundant, as it is also a dependency",
+        redundant_dep.dependency_name.name
+    );
+    let help: String = "remove the dev-dependency, tests can use [dependencies]".to_owned();
+    let level: &'static str = results
+        .redundant_dev_dependency_level(redundant_dep)
+        .report_level();
+
+    let mut spans = Vec::<DiagnosticSpan>::new();
+    if let Some(span) = redundant_dep.manifest_span.as_ref() {
+        spans.push(diagnostic_span(manifest_path, manifest_text, span, true));
+    }
+
+    let removal: Option<ManifestEdit> = manifest::dependency_removal(
+        manifest_text,
+        redundant_dep.dep_kind,
+        redundant_dep.targets.as_slice(),
+        redundant_dep.manifest_key.as_str(),
+    )?;
+    let mut help_spans = Vec::<DiagnosticSpan>::new();
+    if let Some(removal) = removal {
+        let mut help_span: DiagnosticSpan =
+            diagnostic_span(manifest_path, manifest_text, &removal.span, true);
+        help_span.suggested_replacement = Some(removal.replacement);
+        help_span.suggestion_applicability = Some("MachineApplicable");
+        help_spans.push(help_span);
+    }
+
+    return Ok(Message::CompilerMessage {
+        package_id: &redundant_dep.dependant,
+        manifest_path,
+        target: Target {
+            name: results.package_name(&redundant_dep.dependant),
+            kind: ["manifest"],
+            crate_types: [],
+            src_path: manifest_path,
+        },
+        message: Box::new(Diagnostic {
+            rendered: Some(render(
+                level,
+                message.as_str(),
+                manifest_path,
+                manifest_text,
+                redundant_dep.manifest_span.as_ref(),
+                help.as_str(),
+            )),
+            message,
+            code: Some(DiagnosticCode {
+                code: "redundant",
+                explanation: None,
+            }),
+            level,
+            spans,
+            children: vec![Diagnostic {
+                message: help,
+                code: None,
+                level: "help",
+                spans: help_spans,
+                children: Vec::new(),
+                rendered: None,
+            }],
+        }),
+    });
+}
+
+fn optional_candidate_message<'a>(
+    results: &'a DependencyLintResults,
+    optional_candidate: &'a OptionalCandidate,
+    manifest_text: &str,
+) -> Message<'a> {
+    let unused_dep: &UnusedDependency = &optional_candidate.unused_dependency;
+    let manifest_path: &Utf8Path = unused_dep.dependant_manifest_path.as_path();
+    let message: String = format!(
+        "dependency `{}` is only used with the feature `{}`",
+        unused_dep.dependency_name.name, optional_candidate.feature
+    );
+    // Both the entry, and the feature are edited, so there is no suggestion.
+    let help: String = format!(
+        "make it optional, and enable it with `dep:{}` in the feature `{}`",
+        unused_dep.manifest_key, optional_candidate.feature
+    );
+    let level: &'static str = results
+        .optional_candidate_level(optional_candidate)
+        .report_level();
+
+    let mut spans = Vec::<DiagnosticSpan>::new();
+    if let Some(span) = unused_dep.manifest_span.as_ref() {
+        spans.push(diagnostic_span(manifest_path, manifest_text, span, true));
+    }
+
+    return Message::CompilerMessage {
+        package_id: &unused_dep.dependant,
+        manifest_path,
+        target: Target {
+            name: results.package_name(&unused_dep.dependant),
+            kind: ["manifest"],
+            crate_types: [],
+            src_path: manifest_path,
+        },
+        message: Box::new(Diagnostic {
+            rendered: Some(render(
+                level,
+                message.as_str(),
+                manifest_path,
+                manifest_text,
+                unused_dep.manifest_span.as_ref(),
+                help.as_str(),
+            )),
+            message,
+            code: Some(DiagnosticCode {
+                code: "optional",
+                explanation: None,
+            }),
+            level,
+            spans,
+            children: vec![Diagnostic {
+                message: help,
+                code: None,
+                level: "help",
+                spans: Vec::new(),
+                children: Vec::new(),
+                rendered: None,
+            }],
+        }),
+    };
+}
+
+/// `manifests` maps a manifest path to its contents.
+fn messages<'a>(
+    results: &'a DependencyLintResults,
+    manifests: &HashMap<Utf8PathBuf, String>,
+) -> anyhow::Result<Vec<Message<'a>>> {
+    let mut unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+    unused_deps.sort();
+    let mismarked_deps: Vec<(&UnusedDependency, DependencyKind)> =
+        results.mislabeled_dependencies();
+    let mut redundant_deps: Vec<&UnusedDependency> =
+        results.redundant_dev_dependencies.iter().collect();
+    redundant_deps.sort();
+    let mut optional_candidates: Vec<&OptionalCandidate> =
+        results.optional_candidates.iter().collect();
+    optional_candidates.sort();
+
+    let mut messages = Vec::<Message>::with_capacity(
+        unused_deps.len()
+            + mismarked_deps.len()
+            + redundant_deps.len()
+            + optional_candidates.len()
+            + 1,
+    );
+    for unused_dep in unused_deps.into_iter() {
+        let manifest_text: &str = manifests
+            .get(&unused_dep.dependant_manifest_path)
+            .map_or("", String::as_str);
+        messages.push(unused_dependency_message(
+            results,
+            unused_dep,
+            manifest_text,
+        )?);
+    }
+    for (mismarked_dep, dep_kind) in mismarked_deps.into_iter() {
+        let manifest_text: &str = manifests
+            .get(&mismarked_dep.dependant_manifest_path)
+            .map_or("", String::as_str);
+        messages.push(mislabeled_dependency_message(
+            results,
+            mismarked_dep,
+            dep_kind,
+            manifest_text,
+        ));
+    }
+    for redundant_dep in redundant_deps.into_iter() {
+        let manifest_text: &str = manifests
+            .get(&redundant_dep.dependant_manifest_path)
+            .map_or("", String::as_str);
+        messages.push(redundant_dev_dependency_message(
+            results,
+            redundant_dep,
+            manifest_text,
+        )?);
+    }
+    for optional_candidate in optional_candidates.into_iter() {
+        let manifest_text: &str = manifests
+            .get(&optional_candidate.unused_dependency.dependant_manifest_path)
+            .map_or("", String::as_str);
+        messages.push(optional_candidate_message(
+            results,
+            optional_candidate,
+            manifest_text,
+        ));
+    }
+    messages.push(Message::BuildFinished { success: true });
+    return Ok(messages);
+}
+
+pub(crate) fn write_cargo_json_messages(
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let mut manifests = HashMap::<Utf8PathBuf, String>::new();
+    for unused_dep in results
+        .unused_dependencies
+        .iter()
+        .chain(results.mismarked_dev_dependencies.iter())
+        .chain(results.mismarked_build_dependencies.iter())
+        .chain(results.redundant_dev_dependencies.iter())
+        .chain(
+            results
+                .optional_candidates
+                .iter()
+                .map(|optional_candidate| return &optional_candidate.unused_dependency),
+        )
+    {
+        if !manifests.contains_key(&unused_dep.dependant_manifest_path) {
+            manifests.insert(
+                unused_dep.dependant_manifest_path.clone(),
+                std::fs::read_to_string(&unused_dep.dependant_manifest_path)?,
+            );
+        }
+    }
+
+    for message in messages(results, &manifests)?.iter() {
+        serde_json::to_writer(&mut *writer, message)?;
+        writeln!(writer)?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use camino::Utf8PathBuf;
+
+    use crate::test_util;
+
+    #[test]
+    fn test_cargo_json_messages() {
+        let results = test_util::sample_results();
+        let manifests = HashMap::<Utf8PathBuf, String>::from([(
+            Utf8PathBuf::from("/ws/magenta/Cargo.toml"),
+            test_util::MAGENTA_MANIFEST.to_owned(),
+        )]);
+
+        let mut output = Vec::<u8>::new();
+        for message in super::messages(&results, &manifests).unwrap().iter() {
+            serde_json::to_writer(&mut output, message).unwrap();
+            output.push(b'\n');
+        }
+
+        // Must be understood by anything consuming `cargo` messages.
+        let mut compiler_messages = Vec::<cargo_metadata::CompilerMessage>::new();
+        let mut build_finished: bool = false;
+        for message in cargo_metadata::Message::parse_stream(output.as_slice()) {
+            match message.unwrap() {
+                cargo_metadata::Message::CompilerMessage(message) => {
+                    compiler_messages.push(message);
+                }
+                cargo_metadata::Message::BuildFinished(finished) => {
+                    assert!(finished.success);
+                    build_finished = true;
+                }
+                message => {
+                    panic!("unexpected message {:?}", message);
+                }
+            }
+        }
+        assert!(build_finished);
+        assert_eq!(compiler_messages.len(), 5);
+
+        let diagnostic: &cargo_metadata::diagnostic::Diagnostic = &compiler_messages[0].message;
+        assert_eq!(diagnostic.message, "unused dependency `fuchsia`");
+        assert_eq!(
+            diagnostic.level,
+            cargo_metadata::diagnostic::DiagnosticLevel::Error
+        );
+        assert_eq!(diagnostic.code.as_ref().unwrap().code, "unused-normal");
+        assert_eq!(diagnostic.spans.len(), 1);
+        assert_eq!(diagnostic.spans[0].file_name, "/ws/magenta/Cargo.toml");
+        assert_eq!(diagnostic.spans[0].line_start, 6);
+        assert_eq!(diagnostic.spans[0].column_start, 1);
+        assert_eq!(diagnostic.spans[0].column_end, 8);
+        assert_eq!(
+            diagnostic.spans[0].text[0].text,
+            "fuchsia = { path = \"../fuchsia\" }"
+        );
+        assert_eq!(
+            diagnostic.rendered.as_deref(),
+            Some(concat!(
+                "error: unused dependency `fuchsia`\n",
+                " --> /ws/magenta/Cargo.toml:6:1\n",
+                "  |\n",
+                "6 | fuchsia = { path = \"../fuchsia\" }\n",
+                "  | ^^^^^^^\n",
+                "  |\n",
+                "  = help: remove the dependency\n",
+                "\n",
+            ))
+        );
+
+        let help: &cargo_metadata::diagnostic::Diagnostic = &diagnostic.children[0];
+        assert_eq!(
+            help.level,
+            cargo_metadata::diagnostic::DiagnosticLevel::Help
+        );
+        let suggestion: &cargo_metadata::diagnostic::DiagnosticSpan = &help.spans[0];
+        assert_eq!(suggestion.suggested_replacement.as_deref(), Some(""));
+        assert_eq!(
+            suggestion.suggestion_applicability,
+            Some(cargo_metadata::diagnostic::Applicability::MachineApplicable)
+        );
+        assert_eq!(
+            &test_util::MAGENTA_MANIFEST
+                [suggestion.byte_start as usize..suggestion.byte_end as usize],
+            "fuchsia = { path = \"../fuchsia\" }\n"
+        );
+
+        assert_eq!(
+            compiler_messages[2].message.message,
+            "unused dev-dependency `purple`"
+        );
+        assert_eq!(
+            compiler_messages[2].message.level,
+            cargo_metadata::diagnostic::DiagnosticLevel::Warning
+        );
+
+        let diagnostic: &cargo_metadata::diagnostic::Diagnostic = &compiler_messages[3].message;
+        assert_eq!(diagnostic.code.as_ref().unwrap().code, "mislabeled");
+        assert_eq!(
+            diagnostic.rendered.as_deref(),
+            Some(concat!(
+                "warning: dependency `barley` is only used by tests, benches, or examples\n",
+                "  --> /ws/magenta/Cargo.toml:15:1\n",
+                "   |\n",
+                "15 | barley = \"1.0\"\n",
+                "   | ^^^^^^\n",
+                "   |\n",
+                "   = help: move it to [dev-dependencies]\n",
+                "\n",
+            ))
+        );
+
+        let diagnostic: &cargo_metadata::diagnostic::Diagnostic = &compiler_messages[4].message;
+        assert_eq!(diagnostic.code.as_ref().unwrap().code, "optional");
+        assert_eq!(
+            diagnostic.rendered.as_deref(),
+            Some(concat!(
+                "warning: dependency `violet` is only used with the feature `std`\n",
+                "  --> /ws/magenta/Cargo.toml:16:1\n",
+                "   |\n",
+                "16 | violet = \"1.0\"\n",
+                "   | ^^^^^^\n",
+                "   |\n",
+                "   = help: make it optional, and enable it with `dep:violet` in the feature `std`\n",
+                "\n",
+            ))
+        );
+        assert!(diagnostic.children[0].spans.is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+/*
+  A minimal line based unified diff, as printed by `diff -u`, and understood by
+  `patch`, and `git apply`. Manifests are small, so the longest common
+  subsequence is computed with the quadratic dynamic programming algorithm,
+  rather than Myers' algorithm.
+*/
+
+/// The number of unchanged lines printed around each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Edit {
+    /// The line of the old text, and of the new text.
+    Keep(usize, usize),
+    /// The line of the old text.
+    Remove(usize),
+    /// The line of the new text.
+    Insert(usize),
+}
+
+fn line_edits(old_lines: &[&str], new_lines: &[&str]) -> Vec<Edit> {
+    // common[i][j] is the length of the longest common subsequence of
+    // old_lines[i..], and new_lines[j..].
+    let mut common: Vec<Vec<usize>> = vec![vec![0; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            common[i][j] = if old_lines[i] == new_lines[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                std::cmp::max(common[i + 1][j], common[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::<Edit>::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len() {
+        if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
+            edits.push(Edit::Keep(i, j));
+            i += 1;
+            j += 1;
+        } else if j == new_lines.len()
+            || (i < old_lines.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            edits.push(Edit::Remove(i));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(j));
+            j += 1;
+        }
+    }
+    return edits;
+}
+
+fn push_line(diff: &mut String, prefix: char, line: &str) {
+    diff.push(prefix);
+    diff.push_str(line);
+    if !line.ends_with('\n') {
+        diff.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// The `@@ -start,len +start,len @@` range of a hunk, where `start` is the
+/// 1-based first line, or the line before the hunk if it is empty.
+fn hunk_range(first: usize, len: usize) -> String {
+    let start: usize = if len == 0 { first } else { first + 1 };
+    return format!("{},{}", start, len);
+}
+
+/// A unified diff from `old_text` (labelled `old_label`) to `new_text`
+/// (labelled `new_label`), or an empty string if they are identical.
+pub(crate) fn unified_diff(
+    old_label: &str,
+    new_label: &str,
+    old_text: &str,
+    new_text: &str,
+) -> String {
+    let old_lines: Vec<&str> = old_text.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new_text.split_inclusive('\n').collect();
+    let edits: Vec<Edit> = line_edits(&old_lines, &new_lines);
+
+    // Hunks as ranges of edits, changes closer than twice the context share a
+    // hunk.
+    let mut hunks = Vec::<(usize, usize)>::new();
+    for (index, edit) in edits.iter().enumerate() {
+        if let Edit::Keep(_, _) = edit {
+            continue;
+        }
+        let start: usize = index.saturating_sub(CONTEXT_LINES);
+        let end: usize = std::cmp::min(index + 1 + CONTEXT_LINES, edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks.into_iter() {
+        // The position of the hunk in each text, even if it starts with
+        // insertions, or removals.
+        let (mut old_first, mut new_first) = (old_lines.len(), new_lines.len());
+        for edit in edits[start..].iter() {
+            match *edit {
+                Edit::Keep(i, j) => {
+                    old_first = std::cmp::min(old_first, i);
+                    new_first = std::cmp::min(new_first, j);
+                    break;
+                }
+                Edit::Remove(i) => old_first = std::cmp::min(old_first, i),
+                Edit::Insert(j) => new_first = std::cmp::min(new_first, j),
+            }
+        }
+        let mut old_len: usize = 0;
+        let mut new_len: usize = 0;
+        let mut body = String::new();
+        for edit in edits[start..end].iter() {
+            match *edit {
+                Edit::Keep(i, _) => {
+                    old_len += 1;
+                    new_len += 1;
+                    push_line(&mut body, ' ', old_lines[i]);
+                }
+                Edit::Remove(i) => {
+                    old_len += 1;
+                    push_line(&mut body, '-', old_lines[i]);
+                }
+                Edit::Insert(j) => {
+                    new_len += 1;
+                    push_line(&mut body, '+', new_lines[j]);
+                }
+            }
+        }
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_first, old_len),
+            hunk_range(new_first, new_len)
+        ));
+        diff.push_str(&body);
+    }
+    return diff;
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_unified_diff() {
+        struct DiffTest {
+            old_text: &'static str,
+            new_text: &'static str,
+            expected: &'static str,
+        }
+        let diff_tests: &[DiffTest] = &[
+            DiffTest {
+                old_text: "a\nb\n",
+                new_text: "a\nb\n",
+                expected: "",
+            },
+            DiffTest {
+                old_text: "[dependencies]\nfuchsia = \"1\"\npurple = \"1\"\n",
+                new_text: "[dependencies]\npurple = \"1\"\n",
+                expected: concat!(
+                    "--- a/Cargo.toml\n",
+                    "+++ b/Cargo.toml\n",
+                    "@@ -1,3 +1,2 @@\n",
+                    " [dependencies]\n",
+                    "-fuchsia = \"1\"\n",
+                    " purple = \"1\"\n",
+                ),
+            },
+            // Distant changes are separate hunks, and only keep 3 lines of
+            // context.
+            DiffTest {
+                old_text: "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n",
+                new_text: "2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n",
+                expected: concat!(
+                    "--- a/Cargo.toml\n",
+                    "+++ b/Cargo.toml\n",
+                    "@@ -1,4 +1,3 @@\n",
+                    "-1\n",
+                    " 2\n",
+                    " 3\n",
+                    " 4\n",
+                    "@@ -8,3 +7,4 @@\n",
+                    " 8\n",
+                    " 9\n",
+                    " 10\n",
+                    "+11\n",
+                ),
+            },
+            DiffTest {
+                old_text: "a\nb",
+                new_text: "a\n",
+                expected: concat!(
+                    "--- a/Cargo.toml\n",
+                    "+++ b/Cargo.toml\n",
+                    "@@ -1,2 +1,1 @@\n",
+                    " a\n",
+                    "-b\n",
+                    "\\ No newline at end of file\n",
+                ),
+            },
+            DiffTest {
+                old_text: "a\n",
+                new_text: "",
+                expected: concat!(
+                    "--- a/Cargo.toml\n",
+                    "+++ b/Cargo.toml\n",
+                    "@@ -1,1 +0,0 @@\n",
+                    "-a\n",
+                ),
+            },
+        ];
+        for diff_test in diff_tests.iter() {
+            assert_eq!(
+                super::unified_diff(
+                    "a/Cargo.toml",
+                    "b/Cargo.toml",
+                    diff_test.old_text,
+                    diff_test.new_text
+                ),
+                diff_test.expected,
+                "{:?}",
+                diff_test.old_text
+            );
+        }
+    }
+}
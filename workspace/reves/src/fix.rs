@@ -0,0 +1,741 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::str::FromStr;
+
+use anyhow::Context;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::diff;
+use crate::github::relative_path;
+use crate::manifest;
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::Level;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+/// A manifest edited by `--fix`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ManifestFix {
+    pub(crate) original: String,
+    pub(crate) fixed: String,
+}
+
+/// Removes `unused_deps` from their manifests in memory, keyed by manifest
+/// path, along with `[features]` references to dependencies which are no
+/// longer declared. Manifests which are unchanged are omitted.
+pub(crate) fn fix_manifests<'a>(
+    unused_deps: impl Iterator<Item = &'a UnusedDependency>,
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, ManifestFix>> {
+    let mut unused_deps: Vec<&UnusedDependency> = unused_deps.collect();
+    unused_deps.sort();
+
+    let mut documents = BTreeMap::<Utf8PathBuf, (String, toml_edit::Document)>::new();
+    let mut removed_keys = BTreeMap::<Utf8PathBuf, BTreeSet<String>>::new();
+    for unused_dep in unused_deps.into_iter() {
+        let manifest_path: &Utf8PathBuf = &unused_dep.dependant_manifest_path;
+        if !documents.contains_key(manifest_path) {
+            let manifest_data: String = std::fs::read_to_string(manifest_path)?;
+            let document =
+                toml_edit::Document::from_str(manifest_data.as_str()).with_context(|| {
+                    return format!("Failed to parse {}", manifest_path);
+                })?;
+            documents.insert(manifest_path.clone(), (manifest_data, document));
+        }
+        let (_, document) = documents.get_mut(manifest_path).unwrap();
+
+        let removed: usize = manifest::remove_dependency(
+            document,
+            unused_dep.dep_kind,
+            unused_dep.targets.as_slice(),
+            unused_dep.manifest_key.as_str(),
+        );
+        if removed == 0 {
+            eprintln!("Warning: unable to fix {:#?}", unused_dep);
+        } else {
+            removed_keys
+                .entry(manifest_path.clone())
+                .or_default()
+                .insert(unused_dep.manifest_key.clone());
+        }
+    }
+
+    for (manifest_path, keys) in removed_keys.iter() {
+        let (_, document) = documents.get_mut(manifest_path).unwrap();
+        for key in keys.iter() {
+            // Still declared in another table features can enable (such as for
+            // another platform).
+            if !manifest::is_declared_by(
+                document,
+                &[DependencyKind::Normal, DependencyKind::Build],
+                key,
+            ) {
+                manifest::remove_feature_references(document, key);
+            }
+        }
+    }
+
+    let mut fixes = BTreeMap::<Utf8PathBuf, ManifestFix>::new();
+    for (manifest_path, (original, document)) in documents.into_iter() {
+        let fixed: String = document.to_string();
+        if fixed != original {
+            fixes.insert(manifest_path, ManifestFix { original, fixed });
+        }
+    }
+    return Ok(fixes);
+}
+
+/// The text of `manifest_path` after `fixes`, and before them.
+fn manifest_texts(
+    fixes: &BTreeMap<Utf8PathBuf, ManifestFix>,
+    manifest_path: &Utf8PathBuf,
+) -> anyhow::Result<(String, String)> {
+    return match fixes.get(manifest_path) {
+        Some(fix) => Ok((fix.fixed.clone(), fix.original.clone())),
+        None => {
+            let manifest_data: String = std::fs::read_to_string(manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path))?;
+            Ok((manifest_data.clone(), manifest_data))
+        }
+    };
+}
+
+fn inherited_dependency_keys(
+    manifest_path: &Utf8PathBuf,
+    text: &str,
+) -> anyhow::Result<BTreeSet<String>> {
+    let document = toml_edit::Document::from_str(text).with_context(|| {
+        return format!("Failed to parse {}", manifest_path);
+    })?;
+    return Ok(manifest::inherited_dependency_keys(&document));
+}
+
+/// Removes `unused_workspace_deps` from the root manifest, along with entries
+/// which are no longer inherited by any member after `fixes` (unless unused
+/// workspace dependencies are allowed).
+pub(crate) fn prune_workspace_dependencies(
+    results: &DependencyLintResults,
+    unused_workspace_deps: &[&UnusedWorkspaceDependency],
+    fixes: &mut BTreeMap<Utf8PathBuf, ManifestFix>,
+) -> anyhow::Result<()> {
+    let mut removed_keys = BTreeSet::<&str>::new();
+    for unused_workspace_dep in unused_workspace_deps.iter() {
+        removed_keys.insert(unused_workspace_dep.key.as_str());
+    }
+
+    let mut inherited_keys = BTreeSet::<String>::new();
+    let mut previously_inherited_keys = BTreeSet::<String>::new();
+    for manifest_path in results.member_manifest_paths.iter() {
+        let (text, original) = manifest_texts(fixes, manifest_path)?;
+        inherited_keys.append(&mut inherited_dependency_keys(manifest_path, &text)?);
+        previously_inherited_keys.append(&mut inherited_dependency_keys(manifest_path, &original)?);
+    }
+    if results.unused_workspace_dependency_level() != Level::Allow {
+        for key in previously_inherited_keys.difference(&inherited_keys) {
+            removed_keys.insert(key.as_str());
+        }
+    }
+    if removed_keys.is_empty() {
+        return Ok(());
+    }
+
+    let root_manifest_path: Utf8PathBuf = results.workspace_root.join("Cargo.toml");
+    let (text, original) = manifest_texts(fixes, &root_manifest_path)?;
+    let mut document = toml_edit::Document::from_str(text.as_str()).with_context(|| {
+        return format!("Failed to parse {}", root_manifest_path);
+    })?;
+    for key in removed_keys.iter() {
+        manifest::remove_workspace_dependency(&mut document, key);
+    }
+    let fixed: String = document.to_string();
+    if fixed != original {
+        fixes.insert(root_manifest_path, ManifestFix { original, fixed });
+    }
+    return Ok(());
+}
+
+/// Moves `mislabeled_deps` to the table of their kind (`[dev-dependencies]`,
+/// or `[build-dependencies]`), on top of `fixes`. References to them from
+/// `[features]` are removed if they are no longer declared by a table features
+/// can enable.
+pub(crate) fn move_mismarked_dependencies(
+    mislabeled_deps: &[(&UnusedDependency, DependencyKind)],
+    fixes: &mut BTreeMap<Utf8PathBuf, ManifestFix>,
+) -> anyhow::Result<()> {
+    for (mismarked_dep, dep_kind) in mislabeled_deps.iter().copied() {
+        let manifest_path: &Utf8PathBuf = &mismarked_dep.dependant_manifest_path;
+        let (text, original) = manifest_texts(fixes, manifest_path)?;
+        let mut document = toml_edit::Document::from_str(text.as_str()).with_context(|| {
+            return format!("Failed to parse {}", manifest_path);
+        })?;
+        let moved_entries: manifest::MovedEntries = manifest::move_dependency(
+            &mut document,
+            mismarked_dep.targets.as_slice(),
+            mismarked_dep.manifest_key.as_str(),
+            dep_kind,
+        )?;
+        if moved_entries.conflicts > 0 {
+            eprintln!(
+                "Warning: `{}` is already a {} of {} with a different spec, so it wasn't moved",
+                mismarked_dep.manifest_key, dep_kind, manifest_path
+            );
+        }
+        if moved_entries.moved == 0 {
+            if moved_entries.conflicts == 0 {
+                eprintln!("Warning: unable to fix {:#?}", mismarked_dep);
+            }
+            continue;
+        }
+        if !manifest::is_declared_by(
+            &document,
+            &[DependencyKind::Normal, DependencyKind::Build],
+            mismarked_dep.manifest_key.as_str(),
+        ) {
+            manifest::remove_feature_references(&mut document, mismarked_dep.manifest_key.as_str());
+        }
+        let fixed: String = document.to_string();
+        if fixed != original {
+            fixes.insert(manifest_path.clone(), ManifestFix { original, fixed });
+        }
+    }
+    return Ok(());
+}
+
+/// Writes a unified diff of `fixes`, with paths relative to `workspace_root`
+/// (prefixed by `a/`, and `b/` like `git diff`), instead of applying them.
+pub(crate) fn write_diff(
+    workspace_root: &Utf8Path,
+    fixes: &BTreeMap<Utf8PathBuf, ManifestFix>,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    for (manifest_path, fix) in fixes.iter() {
+        let path: String = relative_path(workspace_root, manifest_path);
+        writer.write_all(
+            diff::unified_diff(
+                &format!("a/{}", path),
+                &format!("b/{}", path),
+                &fix.original,
+                &fix.fixed,
+            )
+            .as_bytes(),
+        )?;
+    }
+    return Ok(());
+}
+
+pub(crate) fn write_fixes(fixes: &BTreeMap<Utf8PathBuf, ManifestFix>) -> anyhow::Result<()> {
+    for (manifest_path, fix) in fixes.iter() {
+        std::fs::write(manifest_path, fix.fixed.as_str())
+            .with_context(|| format!("Failed to write {}", manifest_path))?;
+    }
+    return Ok(());
+}
+
+/// A single edit of `--fix`, which is kept, or rolled back on its own by
+/// `write_verified_fixes`.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum FixEdit<'a> {
+    /// Removes an unused dependency.
+    Removal(&'a UnusedDependency),
+    /// Moves a mislabeled dependency to the table of the kind.
+    Move(&'a UnusedDependency, DependencyKind),
+    /// Removes an unused `[workspace.dependencies]` entry.
+    WorkspaceRemoval(&'a UnusedWorkspaceDependency),
+}
+
+impl std::fmt::Display for FixEdit<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            FixEdit::Removal(unused_dep) => write!(
+                f,
+                "the removal of the unused {} `{}` from {}",
+                unused_dep.dep_kind, unused_dep.manifest_key, unused_dep.dependant_manifest_path
+            ),
+            FixEdit::Move(mislabeled_dep, dep_kind) => write!(
+                f,
+                "the move of `{}` to [{}] in {}",
+                mislabeled_dep.manifest_key,
+                manifest::dep_kind_to_toml_key(*dep_kind),
+                mislabeled_dep.dependant_manifest_path
+            ),
+            FixEdit::WorkspaceRemoval(unused_workspace_dep) => write!(
+                f,
+                "the removal of the unused workspace dependency `{}` from {}",
+                unused_workspace_dep.key, unused_workspace_dep.manifest_path
+            ),
+        };
+    }
+}
+
+/// The edits of `--fix`: the removals of `unused_deps`, the moves of the
+/// mislabeled dependencies, and the removals of the unused workspace
+/// dependencies of `results`.
+pub(crate) fn fix_edits<'a>(
+    results: &'a DependencyLintResults,
+    unused_deps: &[&'a UnusedDependency],
+) -> Vec<FixEdit<'a>> {
+    let mut edits = Vec::<FixEdit<'a>>::new();
+    let mut unused_deps: Vec<&UnusedDependency> = unused_deps.to_vec();
+    unused_deps.sort();
+    for unused_dep in unused_deps.into_iter() {
+        edits.push(FixEdit::Removal(unused_dep));
+    }
+    for (mislabeled_dep, dep_kind) in results.mislabeled_dependencies().into_iter() {
+        edits.push(FixEdit::Move(mislabeled_dep, dep_kind));
+    }
+    let mut unused_workspace_deps: Vec<&UnusedWorkspaceDependency> =
+        results.unused_workspace_dependencies.iter().collect();
+    unused_workspace_deps.sort();
+    for unused_workspace_dep in unused_workspace_deps.into_iter() {
+        edits.push(FixEdit::WorkspaceRemoval(unused_workspace_dep));
+    }
+    return edits;
+}
+
+/// The fixes making `edits`, along with pruning the `[workspace.dependencies]`
+/// entries the removals leave unused.
+pub(crate) fn edit_fixes(
+    results: &DependencyLintResults,
+    edits: &[FixEdit<'_>],
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, ManifestFix>> {
+    let mut unused_deps = Vec::<&UnusedDependency>::new();
+    let mut mislabeled_deps = Vec::<(&UnusedDependency, DependencyKind)>::new();
+    let mut unused_workspace_deps = Vec::<&UnusedWorkspaceDependency>::new();
+    for edit in edits.iter() {
+        match edit {
+            FixEdit::Removal(unused_dep) => unused_deps.push(unused_dep),
+            FixEdit::Move(mislabeled_dep, dep_kind) => {
+                mislabeled_deps.push((mislabeled_dep, *dep_kind));
+            }
+            FixEdit::WorkspaceRemoval(unused_workspace_dep) => {
+                unused_workspace_deps.push(unused_workspace_dep);
+            }
+        }
+    }
+    let mut fixes: BTreeMap<Utf8PathBuf, ManifestFix> = fix_manifests(unused_deps.into_iter())?;
+    move_mismarked_dependencies(&mislabeled_deps, &mut fixes)?;
+    prune_workspace_dependencies(results, &unused_workspace_deps, &mut fixes)?;
+    return Ok(fixes);
+}
+
+/// The fixes of `unused_deps`, of the mislabeled dependencies, and of the
+/// `[workspace.dependencies]` entries they leave unused.
+pub(crate) fn unused_dependency_fixes(
+    results: &DependencyLintResults,
+    unused_deps: &[&UnusedDependency],
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, ManifestFix>> {
+    return edit_fixes(results, &fix_edits(results, unused_deps));
+}
+
+fn write_originals(originals: &BTreeMap<Utf8PathBuf, String>) -> anyhow::Result<()> {
+    for (manifest_path, original) in originals.iter() {
+        std::fs::write(manifest_path, original.as_str())
+            .with_context(|| format!("Failed to write {}", manifest_path))?;
+    }
+    return Ok(());
+}
+
+/// The edits kept, and rolled back by `write_verified_fixes`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct VerifiedFixes<'a> {
+    pub(crate) kept: Vec<FixEdit<'a>>,
+    pub(crate) rolled_back: Vec<FixEdit<'a>>,
+}
+
+/// Writes the fixes of `results`, keeping only the edits (removals, moves of
+/// mislabeled dependencies, and removals of unused workspace dependencies) with
+/// which the workspace still `builds`.
+///
+/// All the edits are tried at once first, and if the workspace no longer
+/// builds they are then tried one at a time (on top of the edits kept so far),
+/// so a single breaking edit costs a build per edit. Manifests are restored if
+/// `builds` fails.
+pub(crate) fn write_verified_fixes<'a>(
+    results: &'a DependencyLintResults,
+    builds: &mut dyn FnMut() -> anyhow::Result<bool>,
+) -> anyhow::Result<VerifiedFixes<'a>> {
+    let unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+    let edits: Vec<FixEdit<'a>> = fix_edits(results, &unused_deps);
+
+    let fixes: BTreeMap<Utf8PathBuf, ManifestFix> = edit_fixes(results, &edits)?;
+    // The manifests edited by a subset of the edits are a subset of these.
+    let mut originals = BTreeMap::<Utf8PathBuf, String>::new();
+    for (manifest_path, fix) in fixes.iter() {
+        originals.insert(manifest_path.clone(), fix.original.clone());
+    }
+    write_fixes(&fixes)?;
+    let all_build: anyhow::Result<bool> = builds();
+    match all_build {
+        Ok(true) => {
+            return Ok(VerifiedFixes {
+                kept: edits,
+                rolled_back: Vec::new(),
+            });
+        }
+        Ok(false) => {}
+        Err(e) => {
+            write_originals(&originals)?;
+            return Err(e);
+        }
+    }
+
+    let mut verified_fixes = VerifiedFixes::default();
+    for edit in edits.into_iter() {
+        // The fixes are made on the manifests as they are on disk.
+        write_originals(&originals)?;
+        let mut candidate_edits: Vec<FixEdit<'a>> = verified_fixes.kept.clone();
+        candidate_edits.push(edit);
+        write_fixes(&edit_fixes(results, &candidate_edits)?)?;
+        match builds() {
+            Ok(true) => verified_fixes.kept.push(edit),
+            Ok(false) => verified_fixes.rolled_back.push(edit),
+            Err(e) => {
+                write_originals(&originals)?;
+                return Err(e);
+            }
+        }
+    }
+
+    write_originals(&originals)?;
+    write_fixes(&edit_fixes(results, &verified_fixes.kept)?)?;
+    return Ok(verified_fixes);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
+    use std::collections::HashSet;
+
+    use camino::Utf8PathBuf;
+
+    use super::ManifestFix;
+    use crate::test_util;
+    use crate::DependencyKind;
+    use crate::Level;
+    use crate::UnusedDependency;
+    use crate::UnusedWorkspaceDependency;
+
+    fn testdata_path(file_name: &str) -> Utf8PathBuf {
+        return Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/fix")
+            .join(file_name);
+    }
+
+    #[test]
+    fn test_fix_renamed_dependencies() {
+        struct FixTest {
+            dep_kind: DependencyKind,
+            dependency: &'static str,
+            manifest_key: &'static str,
+            golden: &'static str,
+        }
+        let fix_tests: &[FixTest] = &[
+            FixTest {
+                dep_kind: DependencyKind::Normal,
+                dependency: "fuchsia",
+                manifest_key: "vache",
+                golden: "renamed.normal.fixed.toml",
+            },
+            FixTest {
+                dep_kind: DependencyKind::Development,
+                dependency: "purple",
+                manifest_key: "cochon",
+                golden: "renamed.dev.fixed.toml",
+            },
+            FixTest {
+                dep_kind: DependencyKind::Build,
+                dependency: "fuchsia",
+                manifest_key: "purple",
+                golden: "renamed.build.fixed.toml",
+            },
+        ];
+        let manifest_path: Utf8PathBuf = testdata_path("renamed.toml");
+        for fix_test in fix_tests.iter() {
+            let mut unused_dep: UnusedDependency =
+                test_util::unused_dependency("magenta", fix_test.dependency, fix_test.dep_kind);
+            unused_dep.manifest_key = fix_test.manifest_key.to_owned();
+            unused_dep.dependant_manifest_path = manifest_path.clone();
+
+            let fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+                super::fix_manifests([unused_dep].iter()).unwrap();
+            assert_eq!(fixes.len(), 1, "{}", fix_test.golden);
+            assert_eq!(
+                fixes[&manifest_path].fixed,
+                std::fs::read_to_string(testdata_path(fix_test.golden)).unwrap(),
+                "{}",
+                fix_test.golden
+            );
+        }
+    }
+
+    #[test]
+    fn test_fix_optional_dependencies() {
+        let manifest_path: Utf8PathBuf = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test_workspaces/optional_unused/magenta/Cargo.toml");
+        let mut unused_deps = Vec::<UnusedDependency>::new();
+        for dependency in ["fuchsia", "purple"].iter() {
+            let mut unused_dep: UnusedDependency =
+                test_util::unused_dependency("magenta", dependency, DependencyKind::Normal);
+            unused_dep.dependant_manifest_path = manifest_path.clone();
+            unused_deps.push(unused_dep);
+        }
+
+        let fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+            super::fix_manifests(unused_deps.iter()).unwrap();
+        assert_eq!(
+            fixes[&manifest_path].fixed,
+            std::fs::read_to_string(testdata_path("optional_unused.fixed.toml")).unwrap()
+        );
+
+        // Features can't enable the dev-dependency of the same key.
+        let manifest_path: Utf8PathBuf = testdata_path("optional_dev.toml");
+        let mut unused_dep: UnusedDependency =
+            test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Normal);
+        unused_dep.dependant_manifest_path = manifest_path.clone();
+        let fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+            super::fix_manifests([unused_dep].iter()).unwrap();
+        assert_eq!(
+            fixes[&manifest_path].fixed,
+            std::fs::read_to_string(testdata_path("optional_dev.fixed.toml")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_prune_workspace_dependencies() {
+        let workspace_root: Utf8PathBuf = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test_workspaces/workspace_unused");
+        let magenta_manifest_path: Utf8PathBuf = workspace_root.join("magenta/Cargo.toml");
+        let mut results = test_util::sample_results();
+        results.workspace_root = workspace_root.clone();
+        results.member_manifest_paths = BTreeSet::from([
+            workspace_root.join("fuchsia/Cargo.toml"),
+            magenta_manifest_path.clone(),
+            workspace_root.join("purple/Cargo.toml"),
+        ]);
+        results.unused_workspace_dependencies = HashSet::from([UnusedWorkspaceDependency {
+            key: "magenta".to_owned(),
+            manifest_path: workspace_root.join("Cargo.toml"),
+            manifest_span: None,
+        }]);
+        results.unused_dependencies.clear();
+        for (dependency, dep_kind) in [
+            ("fuchsia", DependencyKind::Normal),
+            ("purple", DependencyKind::Development),
+        ] {
+            let mut unused_dep: UnusedDependency =
+                test_util::unused_dependency("magenta", dependency, dep_kind);
+            unused_dep.dependant_manifest_path = magenta_manifest_path.clone();
+            results.unused_dependencies.insert(unused_dep);
+        }
+
+        let mut fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+            super::fix_manifests(results.unused_dependencies.iter()).unwrap();
+        super::prune_workspace_dependencies(
+            &results,
+            &results
+                .unused_workspace_dependencies
+                .iter()
+                .collect::<Vec<_>>(),
+            &mut fixes,
+        )
+        .unwrap();
+        // `purple` is still inherited by `fuchsia`.
+        assert_eq!(
+            fixes[&workspace_root.join("Cargo.toml")].fixed,
+            std::fs::read_to_string(testdata_path("workspace_unused.fixed.toml")).unwrap()
+        );
+
+        results.workspace_levels.unused_workspace = Level::Allow;
+        results.unused_workspace_dependencies.clear();
+        let mut fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+            super::fix_manifests(results.unused_dependencies.iter()).unwrap();
+        super::prune_workspace_dependencies(
+            &results,
+            &results
+                .unused_workspace_dependencies
+                .iter()
+                .collect::<Vec<_>>(),
+            &mut fixes,
+        )
+        .unwrap();
+        assert!(!fixes.contains_key(&workspace_root.join("Cargo.toml")));
+    }
+
+    #[test]
+    fn test_write_verified_fixes() {
+        let workspace_root: Utf8PathBuf = test_util::copy_test_workspace(
+            "workspace_unused",
+            &["fuchsia", "magenta", "purple"],
+            "verified",
+        );
+        let magenta_manifest_path: Utf8PathBuf = workspace_root.join("magenta/Cargo.toml");
+        let mut results = test_util::sample_results();
+        results.workspace_root = workspace_root.clone();
+        results.member_manifest_paths = BTreeSet::from([
+            workspace_root.join("fuchsia/Cargo.toml"),
+            magenta_manifest_path.clone(),
+            workspace_root.join("purple/Cargo.toml"),
+        ]);
+        results.unused_workspace_dependencies.clear();
+        results.mismarked_dev_dependencies.clear();
+        results.mismarked_build_dependencies.clear();
+        results.unused_dependencies.clear();
+        for (dependency, dep_kind) in [
+            ("fuchsia", DependencyKind::Normal),
+            ("purple", DependencyKind::Development),
+        ] {
+            let mut unused_dep: UnusedDependency =
+                test_util::unused_dependency("magenta", dependency, dep_kind);
+            unused_dep.dependant_manifest_path = magenta_manifest_path.clone();
+            results.unused_dependencies.insert(unused_dep);
+        }
+        let original_magenta: String = std::fs::read_to_string(&magenta_manifest_path)
+            .unwrap()
+            .replace(
+                "fuchsia.workspace = true\n",
+                "fuchsia.workspace = true\nlapin = \"1.0\"\n",
+            );
+        std::fs::write(&magenta_manifest_path, original_magenta.as_str()).unwrap();
+        let mut mislabeled_dep: UnusedDependency =
+            test_util::unused_dependency("magenta", "lapin", DependencyKind::Normal);
+        mislabeled_dep.dependant_manifest_path = magenta_manifest_path.clone();
+        results.mismarked_dev_dependencies.insert(mislabeled_dep);
+        results.unused_workspace_dependencies = HashSet::from([UnusedWorkspaceDependency {
+            key: "magenta".to_owned(),
+            manifest_path: workspace_root.join("Cargo.toml"),
+            manifest_span: None,
+        }]);
+
+        // Pretend the tests of magenta use purple, and its library uses lapin.
+        let mut builds: usize = 0;
+        let verified_fixes = super::write_verified_fixes(&results, &mut || {
+            builds += 1;
+            let text: String = std::fs::read_to_string(&magenta_manifest_path).unwrap();
+            return Ok(text.contains("purple = { workspace = true }")
+                && text.find("lapin") < text.find("[dev-dependencies]"));
+        })
+        .unwrap();
+        let edit_key = |edit: &super::FixEdit<'_>| {
+            return match *edit {
+                super::FixEdit::Removal(unused_dep) => unused_dep.manifest_key.clone(),
+                super::FixEdit::Move(mislabeled_dep, _) => mislabeled_dep.manifest_key.clone(),
+                super::FixEdit::WorkspaceRemoval(unused_workspace_dep) => {
+                    unused_workspace_dep.key.clone()
+                }
+            };
+        };
+        let kept: Vec<String> = verified_fixes.kept.iter().map(edit_key).collect();
+        let rolled_back: Vec<String> = verified_fixes.rolled_back.iter().map(edit_key).collect();
+        assert_eq!(kept, ["fuchsia", "magenta"]);
+        assert_eq!(rolled_back, ["purple", "lapin"]);
+        assert_eq!(builds, 5);
+        assert_eq!(
+            std::fs::read_to_string(&magenta_manifest_path).unwrap(),
+            original_magenta.replace("fuchsia.workspace = true\n", "")
+        );
+        let root_manifest: String =
+            std::fs::read_to_string(workspace_root.join("Cargo.toml")).unwrap();
+        assert!(!root_manifest.contains("fuchsia.path"));
+        assert!(!root_manifest.contains("magenta.path"));
+        assert!(root_manifest.contains("purple.path"));
+
+        // The manifests are restored if the build can't be checked.
+        std::fs::write(&magenta_manifest_path, original_magenta.as_str()).unwrap();
+        let error = super::write_verified_fixes(&results, &mut || {
+            return Err(anyhow::anyhow!("cargo not found"));
+        });
+        assert!(error.is_err());
+        assert_eq!(
+            std::fs::read_to_string(&magenta_manifest_path).unwrap(),
+            original_magenta
+        );
+
+        std::fs::remove_dir_all(&workspace_root).unwrap();
+    }
+
+    #[test]
+    fn test_write_diff() {
+        let workspace_root = Utf8PathBuf::from("/ws");
+        let fixes = BTreeMap::from([
+            (
+                workspace_root.join("Cargo.toml"),
+                ManifestFix {
+                    original: "[workspace.dependencies]\nfuchsia = \"1\"\n".to_owned(),
+                    fixed: "[workspace.dependencies]\n".to_owned(),
+                },
+            ),
+            (
+                workspace_root.join("magenta/Cargo.toml"),
+                ManifestFix {
+                    original: "[dependencies]\nfuchsia.workspace = true\n".to_owned(),
+                    fixed: "[dependencies]\n".to_owned(),
+                },
+            ),
+        ]);
+
+        let mut diff = Vec::<u8>::new();
+        super::write_diff(&workspace_root, &fixes, &mut diff).unwrap();
+        assert_eq!(
+            String::from_utf8(diff).unwrap(),
+            concat!(
+                "--- a/Cargo.toml\n",
+                "+++ b/Cargo.toml\n",
+                "@@ -1,2 +1,1 @@\n",
+                " [workspace.dependencies]\n",
+                "-fuchsia = \"1\"\n",
+                "--- a/magenta/Cargo.toml\n",
+                "+++ b/magenta/Cargo.toml\n",
+                "@@ -1,2 +1,1 @@\n",
+                " [dependencies]\n",
+                "-fuchsia.workspace = true\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_diff_workspace() {
+        let workspace_root: Utf8PathBuf = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test_workspaces/workspace_unused");
+        let root_manifest_path: Utf8PathBuf = workspace_root.join("Cargo.toml");
+        let magenta_manifest_path: Utf8PathBuf = workspace_root.join("magenta/Cargo.toml");
+        let mut results = test_util::sample_results();
+        results.workspace_root = workspace_root.clone();
+        results.member_manifest_paths = BTreeSet::from([
+            workspace_root.join("fuchsia/Cargo.toml"),
+            magenta_manifest_path.clone(),
+            workspace_root.join("purple/Cargo.toml"),
+        ]);
+        results.unused_workspace_dependencies.clear();
+        results.mismarked_dev_dependencies.clear();
+        results.mismarked_build_dependencies.clear();
+        let mut unused_dep: UnusedDependency =
+            test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Normal);
+        unused_dep.dependant_manifest_path = magenta_manifest_path.clone();
+        let fixes: BTreeMap<Utf8PathBuf, ManifestFix> =
+            super::unused_dependency_fixes(&results, &[&unused_dep]).unwrap();
+
+        // `fuchsia` is no longer inherited, so it is pruned from the root
+        // manifest too. The paths are relative to the workspace root, as for
+        // `reves --fix --dry-run | git apply`.
+        let original_root: String = std::fs::read_to_string(&root_manifest_path).unwrap();
+        let original_magenta: String = std::fs::read_to_string(&magenta_manifest_path).unwrap();
+        let mut expected: String = crate::diff::unified_diff(
+            "a/Cargo.toml",
+            "b/Cargo.toml",
+            &original_root,
+            &original_root.replace("fuchsia.path = \"fuchsia\"\n", ""),
+        );
+        expected.push_str(&crate::diff::unified_diff(
+            "a/magenta/Cargo.toml",
+            "b/magenta/Cargo.toml",
+            &original_magenta,
+            &original_magenta.replace("fuchsia.workspace = true\n", ""),
+        ));
+        let mut diff = Vec::<u8>::new();
+        super::write_diff(&workspace_root, &fixes, &mut diff).unwrap();
+        assert_eq!(String::from_utf8(diff).unwrap(), expected);
+    }
+}
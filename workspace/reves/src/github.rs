@@ -0,0 +1,523 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::OptionalCandidate;
+use crate::OrphanArtifact;
+use crate::OrphanArtifactKind;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+/*
+  GitHub Actions workflow commands, see
+  https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions
+*/
+
+fn escape_data(data: &str) -> String {
+    return data
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+}
+
+fn escape_property(property: &str) -> String {
+    return escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C");
+}
+
+/// Paths in annotations must be relative to the repository root, which may
+/// not be the workspace root.
+pub(crate) fn relative_path(base: &Utf8Path, path: &Utf8Path) -> String {
+    return match path.strip_prefix(base) {
+        Ok(relative_path) => relative_path.as_str().replace('\\', "/"),
+        Err(_) => path.as_str().replace('\\', "/"),
+    };
+}
+
+pub(crate) fn orphan_kind_name(kind: &OrphanArtifactKind) -> &'static str {
+    return match kind {
+        OrphanArtifactKind::Bench => "bench",
+        OrphanArtifactKind::Binary => "binary",
+        OrphanArtifactKind::Example => "example",
+        OrphanArtifactKind::Test => "test",
+    };
+}
+
+/// Describes `mismarked_dep`, which should be a `dep_kind` dependency.
+pub(crate) fn mislabeled_dependency_text(
+    results: &DependencyLintResults,
+    mismarked_dep: &UnusedDependency,
+    dep_kind: DependencyKind,
+) -> String {
+    let users: &str = match dep_kind {
+        DependencyKind::Build => "build script",
+        DependencyKind::Normal | DependencyKind::Development => "tests",
+    };
+    return format!(
+        "`{}` is only used by the {} of `{}`, and should be a {}",
+        mismarked_dep.dependency_name.name,
+        users,
+        results.package_name(&mismarked_dep.dependant),
+        dep_kind
+    );
+}
+
+/// Describes `redundant_dep`, a dev-dependency subsumed by a dependency.
+pub(crate) fn redundant_dev_dependency_text(
+    results: &DependencyLintResults,
+    redundant_dep: &UnusedDependency,
+) -> String {
+    return format!(
+        "`{}` is a redundant dev-dependency of `{}`, as it is also a dependency",
+        redundant_dep.dependency_name.name,
+        results.package_name(&redundant_dep.dependant)
+    );
+}
+
+/// Describes `optional_candidate`, a dependency only used with a feature.
+pub(crate) fn optional_candidate_text(
+    results: &DependencyLintResults,
+    optional_candidate: &OptionalCandidate,
+) -> String {
+    return format!(
+        "`{}` is only used by `{}` with the feature `{}`, and could be an optional dependency enabled by it",
+        optional_candidate.unused_dependency.dependency_name.name,
+        results.package_name(&optional_candidate.unused_dependency.dependant),
+        optional_candidate.feature
+    );
+}
+
+#[derive(Default)]
+struct MemberFindings<'a> {
+    unused_deps: Vec<&'a UnusedDependency>,
+    /// With the kind of dependency each should be.
+    mismarked_deps: Vec<(&'a UnusedDependency, DependencyKind)>,
+    redundant_deps: Vec<&'a UnusedDependency>,
+    optional_candidates: Vec<&'a OptionalCandidate>,
+    orphans: Vec<&'a OrphanArtifact>,
+}
+
+/// `[workspace.dependencies]` findings aren't specific to a member.
+fn unused_workspace_dependencies(
+    results: &DependencyLintResults,
+) -> Vec<&UnusedWorkspaceDependency> {
+    let mut unused_workspace_deps: Vec<&UnusedWorkspaceDependency> =
+        results.unused_workspace_dependencies.iter().collect();
+    unused_workspace_deps.sort();
+    return unused_workspace_deps;
+}
+
+/// Findings grouped by workspace member name, sorted by `DependencyKind`.
+fn member_findings(results: &DependencyLintResults) -> BTreeMap<&str, MemberFindings<'_>> {
+    let mut findings = BTreeMap::<&str, MemberFindings>::new();
+    for member in results.workspace_members.values() {
+        findings.insert(member.name.as_str(), MemberFindings::default());
+    }
+    for unused_dep in results.unused_dependencies.iter() {
+        findings
+            .entry(results.package_name(&unused_dep.dependant))
+            .or_default()
+            .unused_deps
+            .push(unused_dep);
+    }
+    for (mismarked_dep, dep_kind) in results.mislabeled_dependencies().into_iter() {
+        findings
+            .entry(results.package_name(&mismarked_dep.dependant))
+            .or_default()
+            .mismarked_deps
+            .push((mismarked_dep, dep_kind));
+    }
+    for redundant_dep in results.redundant_dev_dependencies.iter() {
+        findings
+            .entry(results.package_name(&redundant_dep.dependant))
+            .or_default()
+            .redundant_deps
+            .push(redundant_dep);
+    }
+    for optional_candidate in results.optional_candidates.iter() {
+        findings
+            .entry(results.package_name(&optional_candidate.unused_dependency.dependant))
+            .or_default()
+            .optional_candidates
+            .push(optional_candidate);
+    }
+    for orphan in results.orphans.iter() {
+        findings
+            .entry(results.package_name(&orphan.crate_id))
+            .or_default()
+            .orphans
+            .push(orphan);
+    }
+    for member_findings in findings.values_mut() {
+        member_findings.unused_deps.sort_by(|a, b| {
+            return (a.dep_kind, &a.dependency_name).cmp(&(b.dep_kind, &b.dependency_name));
+        });
+        member_findings.mismarked_deps.sort();
+        member_findings.redundant_deps.sort();
+        member_findings.optional_candidates.sort();
+        member_findings.orphans.sort();
+    }
+    return findings;
+}
+
+/// One `::error` or `::warning` workflow command per finding, depending on its
+/// level.
+fn annotations(results: &DependencyLintResults, base: &Utf8Path) -> String {
+    let mut annotations = String::new();
+    for member_findings in member_findings(results).values() {
+        for unused_dep in member_findings.unused_deps.iter() {
+            let mut properties: String = format!(
+                "file={}",
+                escape_property(&relative_path(base, &unused_dep.dependant_manifest_path))
+            );
+            if let Some(span) = unused_dep.manifest_span.as_ref() {
+                properties.push_str(&format!(
+                    ",line={},endLine={},col={},endColumn={}",
+                    span.line_start, span.line_end, span.column_start, span.column_end
+                ));
+            }
+            properties.push_str(&format!(
+                ",title={}",
+                escape_property(unused_dep.dep_kind.unused_finding_id())
+            ));
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                results.unused_dependency_level(unused_dep).report_level(),
+                properties,
+                escape_data(&format!(
+                    "`{}` is an unused {} of `{}`",
+                    unused_dep.dependency_name.name,
+                    unused_dep.dep_kind,
+                    results.package_name(&unused_dep.dependant)
+                ))
+            ));
+        }
+        for (mismarked_dep, dep_kind) in member_findings.mismarked_deps.iter() {
+            let mut properties: String = format!(
+                "file={}",
+                escape_property(&relative_path(base, &mismarked_dep.dependant_manifest_path))
+            );
+            if let Some(span) = mismarked_dep.manifest_span.as_ref() {
+                properties.push_str(&format!(
+                    ",line={},endLine={},col={},endColumn={}",
+                    span.line_start, span.line_end, span.column_start, span.column_end
+                ));
+            }
+            properties.push_str(",title=mislabeled");
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                results
+                    .mislabeled_dependency_level(mismarked_dep)
+                    .report_level(),
+                properties,
+                escape_data(&mislabeled_dependency_text(
+                    results,
+                    mismarked_dep,
+                    *dep_kind
+                ))
+            ));
+        }
+        for redundant_dep in member_findings.redundant_deps.iter() {
+            let mut properties: String = format!(
+                "file={}",
+                escape_property(&relative_path(base, &redundant_dep.dependant_manifest_path))
+            );
+            if let Some(span) = redundant_dep.manifest_span.as_ref() {
+                properties.push_str(&format!(
+                    ",line={},endLine={},col={},endColumn={}",
+                    span.line_start, span.line_end, span.column_start, span.column_end
+                ));
+            }
+            properties.push_str(",title=redundant");
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                results
+                    .redundant_dev_dependency_level(redundant_dep)
+                    .report_level(),
+                properties,
+                escape_data(&redundant_dev_dependency_text(results, redundant_dep))
+            ));
+        }
+        for optional_candidate in member_findings.optional_candidates.iter() {
+            let unused_dep: &UnusedDependency = &optional_candidate.unused_dependency;
+            let mut properties: String = format!(
+                "file={}",
+                escape_property(&relative_path(base, &unused_dep.dependant_manifest_path))
+            );
+            if let Some(span) = unused_dep.manifest_span.as_ref() {
+                properties.push_str(&format!(
+                    ",line={},endLine={},col={},endColumn={}",
+                    span.line_start, span.line_end, span.column_start, span.column_end
+                ));
+            }
+            properties.push_str(",title=optional");
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                results
+                    .optional_candidate_level(optional_candidate)
+                    .report_level(),
+                properties,
+                escape_data(&optional_candidate_text(results, optional_candidate))
+            ));
+        }
+        for orphan in member_findings.orphans.iter() {
+            let mut properties = String::new();
+            if let Some(path) = results.orphan_path(orphan) {
+                properties.push_str(&format!(
+                    "file={},",
+                    escape_property(&relative_path(base, &path))
+                ));
+            }
+            properties.push_str("title=orphan-artifact");
+            annotations.push_str(&format!(
+                "::{} {}::{}\n",
+                results.orphan_level(orphan).report_level(),
+                properties,
+                escape_data(&format!(
+                    "{} `{}` doesn't use the library of `{}`",
+                    orphan_kind_name(&orphan.kind),
+                    orphan.artifact_name,
+                    results.package_name(&orphan.crate_id)
+                ))
+            ));
+        }
+    }
+    for unused_workspace_dep in unused_workspace_dependencies(results).iter() {
+        let mut properties: String = format!(
+            "file={}",
+            escape_property(&relative_path(base, &unused_workspace_dep.manifest_path))
+        );
+        if let Some(span) = unused_workspace_dep.manifest_span.as_ref() {
+            properties.push_str(&format!(
+                ",line={},endLine={},col={},endColumn={}",
+                span.line_start, span.line_end, span.column_start, span.column_end
+            ));
+        }
+        properties.push_str(",title=unused-workspace");
+        annotations.push_str(&format!(
+            "::{} {}::{}\n",
+            results.unused_workspace_dependency_level().report_level(),
+            properties,
+            escape_data(&format!(
+                "`{}` is an unused workspace dependency",
+                unused_workspace_dep.key
+            ))
+        ));
+    }
+    return annotations;
+}
+
+/// Markdown for `$GITHUB_STEP_SUMMARY`, with a table per workspace member.
+fn step_summary(results: &DependencyLintResults, base: &Utf8Path) -> String {
+    let mut summary = String::new();
+    summary.push_str("## reves\n\n");
+    summary.push_str(&format!(
+        "Found {} unused dependencies, {} mislabeled dependencies, {} redundant dev-dependencies, {} dependencies which could be optional, {} unused workspace dependencies, and {} orphan artifacts.\n\n",
+        results.unused_dependencies.len(),
+        results.mislabeled_dependencies().len(),
+        results.redundant_dev_dependencies.len(),
+        results.optional_candidates.len(),
+        results.unused_workspace_dependencies.len(),
+        results.orphans.len()
+    ));
+
+    let unused_workspace_deps: Vec<&UnusedWorkspaceDependency> =
+        unused_workspace_dependencies(results);
+    if !unused_workspace_deps.is_empty() {
+        summary.push_str("### Workspace\n\n");
+        summary.push_str("| Finding | Name | Location |\n");
+        summary.push_str("|---------|------|----------|\n");
+        for unused_workspace_dep in unused_workspace_deps.iter() {
+            let mut location: String = relative_path(base, &unused_workspace_dep.manifest_path);
+            if let Some(span) = unused_workspace_dep.manifest_span.as_ref() {
+                location.push_str(&format!(":{}", span.line_start));
+            }
+            summary.push_str(&format!(
+                "| unused workspace dependency | `{}` | `{}` |\n",
+                unused_workspace_dep.key, location
+            ));
+        }
+        summary.push('\n');
+    }
+
+    let mut clean_members = Vec::<&str>::new();
+    for (name, member_findings) in member_findings(results).iter() {
+        if member_findings.unused_deps.is_empty()
+            && member_findings.mismarked_deps.is_empty()
+            && member_findings.redundant_deps.is_empty()
+            && member_findings.optional_candidates.is_empty()
+            && member_findings.orphans.is_empty()
+        {
+            clean_members.push(name);
+            continue;
+        }
+        summary.push_str(&format!("### `{}`\n\n", name));
+        summary.push_str("| Finding | Name | Location |\n");
+        summary.push_str("|---------|------|----------|\n");
+        for unused_dep in member_findings.unused_deps.iter() {
+            let mut location: String = relative_path(base, &unused_dep.dependant_manifest_path);
+            if let Some(span) = unused_dep.manifest_span.as_ref() {
+                location.push_str(&format!(":{}", span.line_start));
+            }
+            summary.push_str(&format!(
+                "| unused {} | `{}` | `{}` |\n",
+                unused_dep.dep_kind, unused_dep.dependency_name.name, location
+            ));
+        }
+        for (mismarked_dep, dep_kind) in member_findings.mismarked_deps.iter() {
+            let mut location: String = relative_path(base, &mismarked_dep.dependant_manifest_path);
+            if let Some(span) = mismarked_dep.manifest_span.as_ref() {
+                location.push_str(&format!(":{}", span.line_start));
+            }
+            summary.push_str(&format!(
+                "| should be a {} | `{}` | `{}` |\n",
+                dep_kind, mismarked_dep.dependency_name.name, location
+            ));
+        }
+        for redundant_dep in member_findings.redundant_deps.iter() {
+            let mut location: String = relative_path(base, &redundant_dep.dependant_manifest_path);
+            if let Some(span) = redundant_dep.manifest_span.as_ref() {
+                location.push_str(&format!(":{}", span.line_start));
+            }
+            summary.push_str(&format!(
+                "| redundant dev-dependency | `{}` | `{}` |\n",
+                redundant_dep.dependency_name.name, location
+            ));
+        }
+        for optional_candidate in member_findings.optional_candidates.iter() {
+            let unused_dep: &UnusedDependency = &optional_candidate.unused_dependency;
+            let mut location: String = relative_path(base, &unused_dep.dependant_manifest_path);
+            if let Some(span) = unused_dep.manifest_span.as_ref() {
+                location.push_str(&format!(":{}", span.line_start));
+            }
+            summary.push_str(&format!(
+                "| could be optional with `{}` | `{}` | `{}` |\n",
+                optional_candidate.feature, unused_dep.dependency_name.name, location
+            ));
+        }
+        for orphan in member_findings.orphans.iter() {
+            let location: String = match results.orphan_path(orphan) {
+                Some(path) => relative_path(base, &path),
+                None => orphan.crate_relative_path.to_string(),
+            };
+            summary.push_str(&format!(
+                "| orphan {} | `{}` | `{}` |\n",
+                orphan_kind_name(&orphan.kind),
+                orphan.artifact_name,
+                location
+            ));
+        }
+        summary.push('\n');
+    }
+
+    if !clean_members.is_empty() {
+        summary.push_str("Members without findings: ");
+        for (i, name) in clean_members.iter().enumerate() {
+            if i != 0 {
+                summary.push_str(", ");
+            }
+            summary.push_str(&format!("`{}`", name));
+        }
+        summary.push_str("\n\n");
+    }
+    return summary;
+}
+
+/// Writes the annotations to `writer`, and appends the job summary to
+/// `$GITHUB_STEP_SUMMARY` if set. Paths are relative to `$GITHUB_WORKSPACE`
+/// (the repository root) if set, and the workspace root otherwise.
+pub(crate) fn write_github_report(
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let base: Utf8PathBuf = match std::env::var_os("GITHUB_WORKSPACE") {
+        Some(github_workspace) => {
+            Utf8PathBuf::try_from(std::path::PathBuf::from(github_workspace))?
+        }
+        None => results.workspace_root.clone(),
+    };
+
+    writer.write_all(annotations(results, &base).as_bytes())?;
+
+    if let Some(summary_path) = std::env::var_os("GITHUB_STEP_SUMMARY") {
+        let mut summary_file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(summary_path)?;
+        summary_file.write_all(step_summary(results, &base).as_bytes())?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use camino::Utf8Path;
+
+    use crate::test_util;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(super::escape_data("50%\r\nok: a,b"), "50%25%0D%0Aok: a,b");
+        assert_eq!(super::escape_property("a:b,c%"), "a%3Ab%2Cc%25");
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert_eq!(
+            super::annotations(&test_util::sample_results(), Utf8Path::new("/ws")),
+            concat!(
+                "::error file=magenta/Cargo.toml,line=6,endLine=6,col=1,endColumn=8,title=unused-normal",
+                "::`fuchsia` is an unused dependency of `magenta`\n",
+                "::warning file=magenta/Cargo.toml,line=9,endLine=9,col=1,endColumn=7,title=unused-dev",
+                "::`purple` is an unused dev-dependency of `magenta`\n",
+                "::error file=magenta/Cargo.toml,line=12,endLine=12,col=1,endColumn=8,title=unused-build",
+                "::`fuchsia` is an unused build-dependency of `magenta`\n",
+                "::warning file=magenta/Cargo.toml,line=15,endLine=15,col=1,endColumn=7,title=mislabeled",
+                "::`barley` is only used by the tests of `magenta`, and should be a dev-dependency\n",
+                "::warning file=magenta/Cargo.toml,line=16,endLine=16,col=1,endColumn=7,title=optional",
+                "::`violet` is only used by `magenta` with the feature `std`, and could be an optional dependency enabled by it\n",
+                "::error file=magenta/src/bin/orphan_bin.rs,title=orphan-artifact",
+                "::binary `orphan_bin` doesn't use the library of `magenta`\n",
+                "::error file=Cargo.toml,line=6,endLine=6,col=1,endColumn=6,title=unused-workspace",
+                "::`lapin` is an unused workspace dependency\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_step_summary() {
+        assert_eq!(
+            super::step_summary(&test_util::sample_results(), Utf8Path::new("/ws")),
+            concat!(
+                "## reves\n",
+                "\n",
+                "Found 3 unused dependencies, 1 mislabeled dependencies, 0 redundant dev-dependencies, 1 dependencies which could be optional, 1 unused workspace dependencies, and 1 orphan artifacts.\n",
+                "\n",
+                "### Workspace\n",
+                "\n",
+                "| Finding | Name | Location |\n",
+                "|---------|------|----------|\n",
+                "| unused workspace dependency | `lapin` | `Cargo.toml:6` |\n",
+                "\n",
+                "### `magenta`\n",
+                "\n",
+                "| Finding | Name | Location |\n",
+                "|---------|------|----------|\n",
+                "| unused dependency | `fuchsia` | `magenta/Cargo.toml:6` |\n",
+                "| unused dev-dependency | `purple` | `magenta/Cargo.toml:9` |\n",
+                "| unused build-dependency | `fuchsia` | `magenta/Cargo.toml:12` |\n",
+                "| should be a dev-dependency | `barley` | `magenta/Cargo.toml:15` |\n",
+                "| could be optional with `std` | `violet` | `magenta/Cargo.toml:16` |\n",
+                "| orphan binary | `orphan_bin` | `magenta/src/bin/orphan_bin.rs` |\n",
+                "\n",
+                "Members without findings: `purple`\n",
+                "\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::io::Write;
+
+use camino::Utf8PathBuf;
+
+use crate::fix::ManifestFix;
+use crate::manifest;
+use crate::DependencyLintResults;
+use crate::IgnoredDependency;
+use crate::UnusedDependency;
+
+/*
+  `--fix=interactive` asks about each unused dependency in turn, reading the
+  answers line by line so it can be scripted, for example
+
+    printf 'remove\nignore\nUsed by a macro\n' | reves --fix=interactive
+
+  Unused dependencies which aren't answered (at the end of the input) are kept.
+*/
+
+/// The answer for an unused dependency.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Decision {
+    Keep,
+    Remove,
+    /// Ignore it in `[package.metadata.reves]` with the reason.
+    Ignore(String),
+}
+
+/// The dependency tables declaring `unused_dep`, such as
+/// `[target.'cfg(unix)'.dependencies]`.
+fn table_names(unused_dep: &UnusedDependency) -> Vec<String> {
+    let table_key: &str = manifest::dep_kind_to_toml_key(unused_dep.dep_kind);
+    let mut table_names = Vec::<String>::new();
+    for target in unused_dep.targets.iter() {
+        table_names.push(match target {
+            Some(target) => format!("[target.'{}'.{}]", target, table_key),
+            None => format!("[{}]", table_key),
+        });
+    }
+    return table_names;
+}
+
+/// Reads a line without its line ending, `None` at the end of the input.
+fn read_answer(input: &mut dyn BufRead) -> anyhow::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    return Ok(Some(line.trim().to_owned()));
+}
+
+fn ask_decision(
+    results: &DependencyLintResults,
+    unused_dep: &UnusedDependency,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> anyhow::Result<Option<Decision>> {
+    writeln!(
+        output,
+        "{} has an unused {} `{}`",
+        results.package_name(&unused_dep.dependant),
+        unused_dep.dep_kind,
+        unused_dep.manifest_key
+    )?;
+    writeln!(output, "  manifest: {}", unused_dep.dependant_manifest_path)?;
+    writeln!(output, "  table: {}", table_names(unused_dep).join(", "))?;
+    writeln!(output, "  key: {}", unused_dep.manifest_key)?;
+    let no_artifacts = BTreeSet::<String>::new();
+    let checked_artifacts: &BTreeSet<String> = results
+        .checked_artifacts
+        .get(&(unused_dep.dependant.clone(), unused_dep.dep_kind))
+        .unwrap_or(&no_artifacts);
+    let checked_artifacts: Vec<&str> = checked_artifacts.iter().map(String::as_str).collect();
+    writeln!(output, "  checked: {}", checked_artifacts.join(", "))?;
+
+    loop {
+        write!(output, "[k]eep, [r]emove, or [i]gnore with a reason? ")?;
+        output.flush()?;
+        let answer: String = match read_answer(input)? {
+            Some(answer) => answer,
+            None => return Ok(None),
+        };
+        match answer.as_str() {
+            "k" | "keep" => return Ok(Some(Decision::Keep)),
+            "r" | "remove" => return Ok(Some(Decision::Remove)),
+            "i" | "ignore" => loop {
+                write!(output, "Reason: ")?;
+                output.flush()?;
+                match read_answer(input)? {
+                    Some(reason) if reason.is_empty() => continue,
+                    Some(reason) => return Ok(Some(Decision::Ignore(reason))),
+                    None => return Ok(None),
+                }
+            },
+            _ => writeln!(output, "Unknown answer `{}`", answer)?,
+        }
+    }
+}
+
+/// Asks about each unused dependency of `results`, on `output`, reading the
+/// answers from `input`.
+pub(crate) fn ask_decisions<'a>(
+    results: &'a DependencyLintResults,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> anyhow::Result<Vec<(&'a UnusedDependency, Decision)>> {
+    let mut unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+    unused_deps.sort();
+
+    let mut decisions = Vec::<(&UnusedDependency, Decision)>::new();
+    for unused_dep in unused_deps.into_iter() {
+        match ask_decision(results, unused_dep, input, output)? {
+            Some(decision) => decisions.push((unused_dep, decision)),
+            None => {
+                writeln!(output)?;
+                break;
+            }
+        }
+    }
+    return Ok(decisions);
+}
+
+/// The unused dependencies of `decisions` answered with `remove`.
+pub(crate) fn removed_dependencies(
+    decisions: &[(&UnusedDependency, Decision)],
+) -> HashSet<UnusedDependency> {
+    let mut removed_deps = HashSet::<UnusedDependency>::new();
+    for (unused_dep, decision) in decisions.iter() {
+        if *decision == Decision::Remove {
+            removed_deps.insert((*unused_dep).clone());
+        }
+    }
+    return removed_deps;
+}
+
+/// Leaves only `removed_deps` (those answered with `remove`) to be fixed in
+/// `results`. Mislabeled dependencies, and unused workspace dependencies aren't
+/// asked about, so they aren't fixed either.
+pub(crate) fn retain_removals(
+    results: &mut DependencyLintResults,
+    removed_deps: HashSet<UnusedDependency>,
+) {
+    results.unused_dependencies = removed_deps;
+    results.mismarked_dev_dependencies.clear();
+    results.mismarked_build_dependencies.clear();
+    results.unused_workspace_dependencies.clear();
+}
+
+/// Adds the ignored dependencies of `decisions` to the
+/// `[package.metadata.reves]` of their manifests, in memory.
+pub(crate) fn ignore_dependencies(
+    decisions: &[(&UnusedDependency, Decision)],
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, ManifestFix>> {
+    let mut documents = BTreeMap::<Utf8PathBuf, (String, toml_edit::Document)>::new();
+    for (unused_dep, decision) in decisions.iter() {
+        let reason: &String = match decision {
+            Decision::Ignore(reason) => reason,
+            Decision::Keep | Decision::Remove => continue,
+        };
+        let manifest_path: &Utf8PathBuf = &unused_dep.dependant_manifest_path;
+        if !documents.contains_key(manifest_path) {
+            documents.insert(
+                manifest_path.clone(),
+                manifest::read_manifest(manifest_path)?,
+            );
+        }
+        let (_, document) = documents.get_mut(manifest_path).unwrap();
+        manifest::add_ignored_dependency(
+            document,
+            &IgnoredDependency {
+                name: unused_dep.dependency_name.name.to_string(),
+                kind: Some(unused_dep.dep_kind),
+                reason: reason.clone(),
+            },
+        )?;
+    }
+
+    let mut fixes = BTreeMap::<Utf8PathBuf, ManifestFix>::new();
+    for (manifest_path, (original, document)) in documents.into_iter() {
+        fixes.insert(
+            manifest_path,
+            ManifestFix {
+                original,
+                fixed: document.to_string(),
+            },
+        );
+    }
+    return Ok(fixes);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::collections::HashSet;
+
+    use camino::Utf8PathBuf;
+
+    use super::Decision;
+    use crate::fix;
+    use crate::test_util;
+    use crate::DependencyKind;
+    use crate::UnusedDependency;
+    use crate::UnusedWorkspaceDependency;
+
+    #[test]
+    fn test_ask_decisions() {
+        let results = test_util::sample_results();
+        let mut input: &[u8] = b"remove\nmaybe\ni\n\nUsed by a macro\n";
+        let mut output = Vec::<u8>::new();
+        let decisions = super::ask_decisions(&results, &mut input, &mut output).unwrap();
+
+        let mut answers = Vec::<(&str, Decision)>::new();
+        for (unused_dep, decision) in decisions.into_iter() {
+            answers.push((unused_dep.manifest_key.as_str(), decision));
+        }
+        // The third unused dependency isn't answered, and is kept.
+        assert_eq!(
+            answers,
+            [
+                ("fuchsia", Decision::Remove),
+                ("fuchsia", Decision::Ignore("Used by a macro".to_owned())),
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "magenta has an unused dependency `fuchsia`\n",
+                "  manifest: /ws/magenta/Cargo.toml\n",
+                "  table: [dependencies]\n",
+                "  key: fuchsia\n",
+                "  checked: bin `magenta`, bin `magenta` (test), bin `orphan_bin`\n",
+                "[k]eep, [r]emove, or [i]gnore with a reason? ",
+                "magenta has an unused build-dependency `fuchsia`\n",
+                "  manifest: /ws/magenta/Cargo.toml\n",
+                "  table: [build-dependencies]\n",
+                "  key: fuchsia\n",
+                "  checked: custom-build `build-script-build`\n",
+                "[k]eep, [r]emove, or [i]gnore with a reason? ",
+                "Unknown answer `maybe`\n",
+                "[k]eep, [r]emove, or [i]gnore with a reason? ",
+                "Reason: Reason: ",
+                "magenta has an unused dev-dependency `purple`\n",
+                "  manifest: /ws/magenta/Cargo.toml\n",
+                "  table: [dev-dependencies]\n",
+                "  key: purple\n",
+                "  checked: bin `magenta` (test)\n",
+                "[k]eep, [r]emove, or [i]gnore with a reason? \n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_keep_every_dependency() {
+        let workspace_root: Utf8PathBuf = test_util::copy_test_workspace(
+            "workspace_unused",
+            &["fuchsia", "magenta", "purple"],
+            "interactive",
+        );
+        let magenta_manifest_path: Utf8PathBuf = workspace_root.join("magenta/Cargo.toml");
+        let mut results = test_util::sample_results();
+        results.workspace_root = workspace_root.clone();
+        results.member_manifest_paths = BTreeSet::from([
+            workspace_root.join("fuchsia/Cargo.toml"),
+            magenta_manifest_path.clone(),
+            workspace_root.join("purple/Cargo.toml"),
+        ]);
+        results.unused_dependencies.clear();
+        for (dependency, dep_kind) in [
+            ("fuchsia", DependencyKind::Normal),
+            ("purple", DependencyKind::Development),
+        ] {
+            let mut unused_dep: UnusedDependency =
+                test_util::unused_dependency("magenta", dependency, dep_kind);
+            unused_dep.dependant_manifest_path = magenta_manifest_path.clone();
+            results.unused_dependencies.insert(unused_dep);
+        }
+        // Fixed by `--fix`, but not asked about.
+        let mut mislabeled_dep: UnusedDependency =
+            test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Normal);
+        mislabeled_dep.dependant_manifest_path = magenta_manifest_path.clone();
+        results.mismarked_dev_dependencies = HashSet::from([mislabeled_dep]);
+        results.unused_workspace_dependencies = HashSet::from([UnusedWorkspaceDependency {
+            key: "magenta".to_owned(),
+            manifest_path: workspace_root.join("Cargo.toml"),
+            manifest_span: None,
+        }]);
+
+        let mut input: &[u8] = b"keep\nk\n";
+        let mut output = Vec::<u8>::new();
+        let decisions = super::ask_decisions(&results, &mut input, &mut output).unwrap();
+        assert_eq!(decisions.len(), 2);
+        assert!(super::ignore_dependencies(&decisions).unwrap().is_empty());
+        let removed_deps: HashSet<UnusedDependency> = super::removed_dependencies(&decisions);
+        assert!(removed_deps.is_empty());
+        super::retain_removals(&mut results, removed_deps);
+        let unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+        assert!(fix::unused_dependency_fixes(&results, &unused_deps)
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(&workspace_root).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use std::io::Write;
+
+use crate::DependencyLintResults;
+
+/// Bumped whenever a field is removed, renamed, or changes meaning. Adding new
+/// fields is not considered a breaking change.
+pub(crate) const JSON_REPORT_VERSION: u32 = 1;
+
+#[derive(serde::Serialize)]
+struct JsonReport<'a> {
+    version: u32,
+    #[serde(flatten)]
+    results: &'a DependencyLintResults,
+}
+
+pub(crate) fn write_json_report(
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(
+        &mut *writer,
+        &JsonReport {
+            version: JSON_REPORT_VERSION,
+            results,
+        },
+    )?;
+    writeln!(writer)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util;
+    use crate::DependencyKind;
+    use crate::SuppressedDependency;
+    use crate::Suppression;
+
+    #[test]
+    fn test_json_report() {
+        let mut results = test_util::sample_results();
+        results
+            .suppressed_dependencies
+            .insert(SuppressedDependency {
+                unused_dependency: test_util::unused_dependency(
+                    "purple",
+                    "backtrace",
+                    DependencyKind::Normal,
+                ),
+                suppressed_by: Suppression::KeepComment,
+                reason: Some("version floor".to_owned()),
+            });
+        let mut output = Vec::<u8>::new();
+        super::write_json_report(&results, &mut output).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(output.as_slice()).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "version": 1,
+                "workspace_root": "/ws",
+                "workspace_members": {
+                    "magenta": {
+                        "name": "magenta",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "levels": {
+                            "unused-normal": "deny",
+                            "unused-dev": "warn",
+                            "unused-build": "deny",
+                            "orphans": "deny",
+                            "unused-workspace": "deny",
+                            "mislabeled": "warn",
+                            "redundant": "warn",
+                            "optional": "warn",
+                        },
+                        "ignored": [],
+                    },
+                    "purple": {
+                        "name": "purple",
+                        "manifest_path": "/ws/purple/Cargo.toml",
+                        "levels": {
+                            "unused-normal": "deny",
+                            "unused-dev": "deny",
+                            "unused-build": "deny",
+                            "orphans": "deny",
+                            "unused-workspace": "deny",
+                            "mislabeled": "warn",
+                            "redundant": "warn",
+                            "optional": "warn",
+                        },
+                        "ignored": [],
+                    },
+                },
+                "unused_dependencies": [
+                    {
+                        "dependant": "magenta",
+                        "dependency": "fuchsia",
+                        "dep_kind": "normal",
+                        "dependency_name": "fuchsia",
+                        "manifest_key": "fuchsia",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "targets": [null],
+                        "manifest_span": {
+                            "byte_start": 61,
+                            "byte_end": 68,
+                            "line_start": 6,
+                            "line_end": 6,
+                            "column_start": 1,
+                            "column_end": 8,
+                        },
+                    },
+                    {
+                        "dependant": "magenta",
+                        "dependency": "fuchsia",
+                        "dep_kind": "build",
+                        "dependency_name": "fuchsia",
+                        "manifest_key": "fuchsia",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "targets": [null],
+                        "manifest_span": {
+                            "byte_start": 169,
+                            "byte_end": 176,
+                            "line_start": 12,
+                            "line_end": 12,
+                            "column_start": 1,
+                            "column_end": 8,
+                        },
+                    },
+                    {
+                        "dependant": "magenta",
+                        "dependency": "purple",
+                        "dep_kind": "dev",
+                        "dependency_name": "purple",
+                        "manifest_key": "purple",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "targets": [null],
+                        "manifest_span": {
+                            "byte_start": 115,
+                            "byte_end": 121,
+                            "line_start": 9,
+                            "line_end": 9,
+                            "column_start": 1,
+                            "column_end": 7,
+                        },
+                    },
+                ],
+                "suppressed_dependencies": [
+                    {
+                        "dependant": "purple",
+                        "dependency": "backtrace",
+                        "dep_kind": "normal",
+                        "dependency_name": "backtrace",
+                        "manifest_key": "backtrace",
+                        "manifest_path": "/ws/purple/Cargo.toml",
+                        "targets": [null],
+                        "manifest_span": null,
+                        "suppressed_by": "keep-comment",
+                        "reason": "version floor",
+                    },
+                ],
+                "mismarked_dev_dependencies": [
+                    {
+                        "dependant": "magenta",
+                        "dependency": "barley",
+                        "dep_kind": "normal",
+                        "dependency_name": "barley",
+                        "manifest_key": "barley",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "targets": ["cfg(unix)"],
+                        "manifest_span": {
+                            "byte_start": 238,
+                            "byte_end": 244,
+                            "line_start": 15,
+                            "line_end": 15,
+                            "column_start": 1,
+                            "column_end": 7,
+                        },
+                    },
+                ],
+                "mismarked_build_dependencies": [],
+                "redundant_dev_dependencies": [],
+                "optional_candidates": [
+                    {
+                        "dependant": "magenta",
+                        "dependency": "violet",
+                        "dep_kind": "normal",
+                        "dependency_name": "violet",
+                        "manifest_key": "violet",
+                        "manifest_path": "/ws/magenta/Cargo.toml",
+                        "targets": ["cfg(unix)"],
+                        "manifest_span": {
+                            "byte_start": 253,
+                            "byte_end": 259,
+                            "line_start": 16,
+                            "line_end": 16,
+                            "column_start": 1,
+                            "column_end": 7,
+                        },
+                        "feature": "std",
+                    },
+                ],
+                "fixed_baseline_entries": [],
+                "unused_workspace_dependencies": [
+                    {
+                        "key": "lapin",
+                        "manifest_path": "/ws/Cargo.toml",
+                        "manifest_span": {
+                            "byte_start": 101,
+                            "byte_end": 106,
+                            "line_start": 6,
+                            "line_end": 6,
+                            "column_start": 1,
+                            "column_end": 6,
+                        },
+                    },
+                ],
+                "workspace_levels": {
+                    "unused-normal": "deny",
+                    "unused-dev": "deny",
+                    "unused-build": "deny",
+                    "orphans": "deny",
+                    "unused-workspace": "deny",
+                    "mislabeled": "warn",
+                    "redundant": "warn",
+                    "optional": "warn",
+                },
+                "orphans": [
+                    {
+                        "crate_id": "magenta",
+                        "kind": "binary",
+                        "artifact_name": "orphan_bin",
+                        "crate_relative_path": "src/bin/orphan_bin.rs",
+                    },
+                ],
+            })
+        );
+    }
+}
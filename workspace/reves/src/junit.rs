@@ -0,0 +1,393 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::github::mislabeled_dependency_text;
+use crate::github::optional_candidate_text;
+use crate::github::orphan_kind_name;
+use crate::github::redundant_dev_dependency_text;
+use crate::github::relative_path;
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::OptionalCandidate;
+use crate::OrphanArtifact;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+/*
+  The JUnit XML format isn't formally specified, this follows what Jenkins, and
+  most CI systems accept: a <testsuite> per workspace member, a failing
+  <testcase> per finding, and a single passing <testcase> for members without
+  findings.
+*/
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+struct TestCase {
+    name: String,
+    file: Option<String>,
+    line: Option<usize>,
+    /// `(type, message)` of the failure, `None` if passing.
+    failure: Option<(&'static str, String)>,
+}
+
+fn unused_dependency_test_case(
+    results: &DependencyLintResults,
+    unused_dep: &UnusedDependency,
+) -> TestCase {
+    return TestCase {
+        name: format!(
+            "{} {}",
+            unused_dep.dep_kind.unused_finding_id(),
+            unused_dep.manifest_key
+        ),
+        file: Some(relative_path(
+            &results.workspace_root,
+            &unused_dep.dependant_manifest_path,
+        )),
+        line: unused_dep.manifest_span.map(|span| {
+            return span.line_start;
+        }),
+        failure: Some((
+            unused_dep.dep_kind.unused_finding_id(),
+            format!(
+                "`{}` is an unused {} of `{}`",
+                unused_dep.dependency_name.name,
+                unused_dep.dep_kind,
+                results.package_name(&unused_dep.dependant)
+            ),
+        )),
+    };
+}
+
+/// `dep_kind` is the kind of dependency `mismarked_dep` should be.
+fn mislabeled_dependency_test_case(
+    results: &DependencyLintResults,
+    mismarked_dep: &UnusedDependency,
+    dep_kind: DependencyKind,
+) -> TestCase {
+    return TestCase {
+        name: format!("mislabeled {}", mismarked_dep.manifest_key),
+        file: Some(relative_path(
+            &results.workspace_root,
+            &mismarked_dep.dependant_manifest_path,
+        )),
+        line: mismarked_dep.manifest_span.map(|span| {
+            return span.line_start;
+        }),
+        failure: Some((
+            "mislabeled",
+            mislabeled_dependency_text(results, mismarked_dep, dep_kind),
+        )),
+    };
+}
+
+fn redundant_dev_dependency_test_case(
+    results: &DependencyLintResults,
+    redundant_dep: &UnusedDependency,
+) -> TestCase {
+    return TestCase {
+        name: format!("redundant {}", redundant_dep.manifest_key),
+        file: Some(relative_path(
+            &results.workspace_root,
+            &redundant_dep.dependant_manifest_path,
+        )),
+        line: redundant_dep.manifest_span.map(|span| {
+            return span.line_start;
+        }),
+        failure: Some((
+            "redundant",
+            redundant_dev_dependency_text(results, redundant_dep),
+        )),
+    };
+}
+
+fn optional_candidate_test_case(
+    results: &DependencyLintResults,
+    optional_candidate: &OptionalCandidate,
+) -> TestCase {
+    let unused_dep: &UnusedDependency = &optional_candidate.unused_dependency;
+    return TestCase {
+        name: format!("optional {}", unused_dep.manifest_key),
+        file: Some(relative_path(
+            &results.workspace_root,
+            &unused_dep.dependant_manifest_path,
+        )),
+        line: unused_dep.manifest_span.map(|span| {
+            return span.line_start;
+        }),
+        failure: Some((
+            "optional",
+            optional_candidate_text(results, optional_candidate),
+        )),
+    };
+}
+
+fn orphan_test_case(results: &DependencyLintResults, orphan: &OrphanArtifact) -> TestCase {
+    return TestCase {
+        name: format!("orphan-artifact {}", orphan.artifact_name),
+        file: results.orphan_path(orphan).map(|path| {
+            return relative_path(&results.workspace_root, &path);
+        }),
+        line: None,
+        failure: Some((
+            "orphan-artifact",
+            format!(
+                "{} `{}` doesn't use the library of `{}`",
+                orphan_kind_name(&orphan.kind),
+                orphan.artifact_name,
+                results.package_name(&orphan.crate_id)
+            ),
+        )),
+    };
+}
+
+/// `[workspace.dependencies]` findings aren't specific to a member, so are in a
+/// test suite named so it can't clash with a package name.
+const WORKSPACE_SUITE: &str = "[workspace]";
+
+fn unused_workspace_dependency_test_case(
+    results: &DependencyLintResults,
+    unused_workspace_dep: &UnusedWorkspaceDependency,
+) -> TestCase {
+    return TestCase {
+        name: format!("unused-workspace {}", unused_workspace_dep.key),
+        file: Some(relative_path(
+            &results.workspace_root,
+            &unused_workspace_dep.manifest_path,
+        )),
+        line: unused_workspace_dep.manifest_span.map(|span| {
+            return span.line_start;
+        }),
+        failure: Some((
+            "unused-workspace",
+            format!(
+                "`{}` is an unused workspace dependency",
+                unused_workspace_dep.key
+            ),
+        )),
+    };
+}
+
+fn test_suites(results: &DependencyLintResults) -> BTreeMap<&str, Vec<TestCase>> {
+    let mut unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+    unused_deps.sort();
+    let mismarked_deps: Vec<(&UnusedDependency, DependencyKind)> =
+        results.mislabeled_dependencies();
+    let mut redundant_deps: Vec<&UnusedDependency> =
+        results.redundant_dev_dependencies.iter().collect();
+    redundant_deps.sort();
+    let mut optional_candidates: Vec<&OptionalCandidate> =
+        results.optional_candidates.iter().collect();
+    optional_candidates.sort();
+    let mut orphans: Vec<&OrphanArtifact> = results.orphans.iter().collect();
+    orphans.sort();
+    let mut unused_workspace_deps: Vec<&UnusedWorkspaceDependency> =
+        results.unused_workspace_dependencies.iter().collect();
+    unused_workspace_deps.sort();
+
+    let mut suites = BTreeMap::<&str, Vec<TestCase>>::new();
+    for member in results.workspace_members.values() {
+        suites.insert(member.name.as_str(), Vec::new());
+    }
+    for unused_dep in unused_deps.into_iter() {
+        suites
+            .entry(results.package_name(&unused_dep.dependant))
+            .or_default()
+            .push(unused_dependency_test_case(results, unused_dep));
+    }
+    for (mismarked_dep, dep_kind) in mismarked_deps.into_iter() {
+        suites
+            .entry(results.package_name(&mismarked_dep.dependant))
+            .or_default()
+            .push(mislabeled_dependency_test_case(
+                results,
+                mismarked_dep,
+                dep_kind,
+            ));
+    }
+    for redundant_dep in redundant_deps.into_iter() {
+        suites
+            .entry(results.package_name(&redundant_dep.dependant))
+            .or_default()
+            .push(redundant_dev_dependency_test_case(results, redundant_dep));
+    }
+    for optional_candidate in optional_candidates.into_iter() {
+        suites
+            .entry(results.package_name(&optional_candidate.unused_dependency.dependant))
+            .or_default()
+            .push(optional_candidate_test_case(results, optional_candidate));
+    }
+    for orphan in orphans.into_iter() {
+        suites
+            .entry(results.package_name(&orphan.crate_id))
+            .or_default()
+            .push(orphan_test_case(results, orphan));
+    }
+    for unused_workspace_dep in unused_workspace_deps.into_iter() {
+        suites
+            .entry(WORKSPACE_SUITE)
+            .or_default()
+            .push(unused_workspace_dependency_test_case(
+                results,
+                unused_workspace_dep,
+            ));
+    }
+    for (name, test_cases) in suites.iter_mut() {
+        if test_cases.is_empty() {
+            test_cases.push(TestCase {
+                name: "unused dependencies".to_owned(),
+                file: results
+                    .workspace_members
+                    .values()
+                    .find(|member| {
+                        return member.name == *name;
+                    })
+                    .map(|member| {
+                        return relative_path(&results.workspace_root, &member.manifest_path);
+                    }),
+                line: None,
+                failure: None,
+            });
+        }
+    }
+    return suites;
+}
+
+fn junit_report(results: &DependencyLintResults) -> String {
+    let suites: BTreeMap<&str, Vec<TestCase>> = test_suites(results);
+    let mut total_tests: usize = 0;
+    let mut total_failures: usize = 0;
+    for test_cases in suites.values() {
+        total_tests += test_cases.len();
+        for test_case in test_cases.iter() {
+            if test_case.failure.is_some() {
+                total_failures += 1;
+            }
+        }
+    }
+
+    let mut report = String::new();
+    report.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    report.push_str(&format!(
+        "<testsuites name=\"reves\" tests=\"{}\" failures=\"{}\">\n",
+        total_tests, total_failures
+    ));
+    for (name, test_cases) in suites.iter() {
+        let mut failures: usize = 0;
+        for test_case in test_cases.iter() {
+            if test_case.failure.is_some() {
+                failures += 1;
+            }
+        }
+        report.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+            escape_xml(name),
+            test_cases.len(),
+            failures
+        ));
+        for test_case in test_cases.iter() {
+            report.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape_xml(name),
+                escape_xml(&test_case.name)
+            ));
+            if let Some(file) = test_case.file.as_ref() {
+                report.push_str(&format!(" file=\"{}\"", escape_xml(file)));
+            }
+            if let Some(line) = test_case.line {
+                report.push_str(&format!(" line=\"{}\"", line));
+            }
+            match test_case.failure.as_ref() {
+                Some((failure_type, message)) => {
+                    report.push_str(">\n");
+                    report.push_str(&format!(
+                        "      <failure type=\"{}\" message=\"{}\"/>\n",
+                        escape_xml(failure_type),
+                        escape_xml(message)
+                    ));
+                    report.push_str("    </testcase>\n");
+                }
+                None => {
+                    report.push_str("/>\n");
+                }
+            }
+        }
+        report.push_str("  </testsuite>\n");
+    }
+    report.push_str("</testsuites>\n");
+    return report;
+}
+
+pub(crate) fn write_junit_report(
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    writer.write_all(junit_report(results).as_bytes())?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util;
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            super::escape_xml("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_junit_report() {
+        assert_eq!(
+            super::junit_report(&test_util::sample_results()),
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites name=\"reves\" tests=\"8\" failures=\"7\">\n",
+                "  <testsuite name=\"[workspace]\" tests=\"1\" failures=\"1\" errors=\"0\" skipped=\"0\">\n",
+                "    <testcase classname=\"[workspace]\" name=\"unused-workspace lapin\" file=\"Cargo.toml\" line=\"6\">\n",
+                "      <failure type=\"unused-workspace\" message=\"`lapin` is an unused workspace dependency\"/>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n",
+                "  <testsuite name=\"magenta\" tests=\"6\" failures=\"6\" errors=\"0\" skipped=\"0\">\n",
+                "    <testcase classname=\"magenta\" name=\"unused-normal fuchsia\" file=\"magenta/Cargo.toml\" line=\"6\">\n",
+                "      <failure type=\"unused-normal\" message=\"`fuchsia` is an unused dependency of `magenta`\"/>\n",
+                "    </testcase>\n",
+                "    <testcase classname=\"magenta\" name=\"unused-build fuchsia\" file=\"magenta/Cargo.toml\" line=\"12\">\n",
+                "      <failure type=\"unused-build\" message=\"`fuchsia` is an unused build-dependency of `magenta`\"/>\n",
+                "    </testcase>\n",
+                "    <testcase classname=\"magenta\" name=\"unused-dev purple\" file=\"magenta/Cargo.toml\" line=\"9\">\n",
+                "      <failure type=\"unused-dev\" message=\"`purple` is an unused dev-dependency of `magenta`\"/>\n",
+                "    </testcase>\n",
+                "    <testcase classname=\"magenta\" name=\"mislabeled barley\" file=\"magenta/Cargo.toml\" line=\"15\">\n",
+                "      <failure type=\"mislabeled\" message=\"`barley` is only used by the tests of `magenta`, and should be a dev-dependency\"/>\n",
+                "    </testcase>\n",
+                "    <testcase classname=\"magenta\" name=\"optional violet\" file=\"magenta/Cargo.toml\" line=\"16\">\n",
+                "      <failure type=\"optional\" message=\"`violet` is only used by `magenta` with the feature `std`, and could be an optional dependency enabled by it\"/>\n",
+                "    </testcase>\n",
+                "    <testcase classname=\"magenta\" name=\"orphan-artifact orphan_bin\" file=\"magenta/src/bin/orphan_bin.rs\">\n",
+                "      <failure type=\"orphan-artifact\" message=\"binary `orphan_bin` doesn&apos;t use the library of `magenta`\"/>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n",
+                "  <testsuite name=\"purple\" tests=\"1\" failures=\"0\" errors=\"0\" skipped=\"0\">\n",
+                "    <testcase classname=\"purple\" name=\"unused dependencies\" file=\"purple/Cargo.toml\"/>\n",
+                "  </testsuite>\n",
+                "</testsuites>\n",
+            )
+        );
+    }
+}
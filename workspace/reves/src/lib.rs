@@ -25,6 +25,7 @@
 use std::borrow::Borrow;
 use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsStr;
@@ -32,19 +33,39 @@ use std::ffi::OsString;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
 use std::process::ExitStatus;
-use std::str::FromStr;
 
 use anyhow::Context;
-use camino::Utf8Path;
 use camino::Utf8PathBuf;
 use cargo_metadata::semver;
 use regex::Regex;
 
+pub use baseline::BaselineEntry;
+pub use config::IgnoredDependency;
+pub use config::Level;
+pub use config::Levels;
+pub use manifest::ManifestSpan;
+
+mod baseline;
 mod cargo_links;
+mod config;
+mod diagnostics;
+mod diff;
+mod fix;
+mod github;
+mod interactive;
+mod json;
+mod junit;
+mod manifest;
+mod plan;
+mod platform;
+mod sarif;
+#[cfg(test)]
+mod test_util;
 
 #[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 struct UnusedExterns {
@@ -52,7 +73,8 @@ struct UnusedExterns {
     unused_extern_names: Vec<String>,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+#[serde(transparent)]
 struct UnrenamedCrate<'a> {
     name: Cow<'a, str>,
 }
@@ -95,36 +117,465 @@ enum CheckTarget {
     Target(String),
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+// Serialized with the same names as `cargo metadata` uses.
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
 pub enum DependencyKind {
+    #[serde(rename = "normal")]
     Normal,
+    #[serde(rename = "dev")]
     Development,
+    #[serde(rename = "build")]
     Build,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+impl DependencyKind {
+    /// Identifies unused dependencies of this kind in reports.
+    pub(crate) fn unused_finding_id(self) -> &'static str {
+        return match self {
+            DependencyKind::Normal => "unused-normal",
+            DependencyKind::Development => "unused-dev",
+            DependencyKind::Build => "unused-build",
+        };
+    }
+
+    /// The serialized name, as written in the `kind` of an ignored dependency.
+    pub(crate) fn serde_name(self) -> String {
+        let value: serde_json::Value = serde_json::to_value(self).unwrap();
+        return value.as_str().unwrap().to_owned();
+    }
+}
+
+impl std::fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.write_str(match self {
+            DependencyKind::Normal => "dependency",
+            DependencyKind::Development => "dev-dependency",
+            DependencyKind::Build => "build-dependency",
+        });
+    }
+}
+
+/// Categories of findings, as selected with `--deny`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, clap::ValueEnum)]
+pub enum LintCategory {
+    UnusedNormal,
+    UnusedDev,
+    UnusedBuild,
+    Orphans,
+    UnusedWorkspace,
+    Mislabeled,
+    Redundant,
+    Optional,
+}
+
+impl LintCategory {
+    pub const ALL: [LintCategory; 8] = [
+        LintCategory::UnusedNormal,
+        LintCategory::UnusedDev,
+        LintCategory::UnusedBuild,
+        LintCategory::Orphans,
+        LintCategory::UnusedWorkspace,
+        LintCategory::Mislabeled,
+        LintCategory::Redundant,
+        LintCategory::Optional,
+    ];
+
+    pub fn of_unused_dependency(dep_kind: DependencyKind) -> LintCategory {
+        return match dep_kind {
+            DependencyKind::Normal => LintCategory::UnusedNormal,
+            DependencyKind::Development => LintCategory::UnusedDev,
+            DependencyKind::Build => LintCategory::UnusedBuild,
+        };
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
 pub struct UnusedDependency {
     pub dependant: cargo_metadata::PackageId,
     pub dependency: cargo_metadata::PackageId,
     pub dep_kind: DependencyKind,
 
     dependency_name: UnrenamedCrateOwned,
+    /// The key of the dependency within its dependency tables, which is the
+    /// rename given by `package = "..."` if there is one, otherwise the name of
+    /// the dependency's package.
+    pub manifest_key: String,
+    #[serde(rename = "manifest_path")]
     dependant_manifest_path: Utf8PathBuf,
+    /// The platforms (a target triple, or `cfg(...)` expression) of the
+    /// `[target.<platform>.*dependencies]` tables declaring the dependency
+    /// which are active for the checked platform, `None` for the table which
+    /// applies to every platform.
+    pub targets: Vec<Option<String>>,
+    /// The key of the dependency within `manifest_path`. `None` if the key
+    /// couldn't be found (such as dependencies declared in an unusual way).
+    pub manifest_span: Option<ManifestSpan>,
+}
+
+/// A `[workspace.dependencies]` entry which no workspace member inherits.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+pub struct UnusedWorkspaceDependency {
+    /// The key of the entry, as inherited with `key.workspace = true`.
+    pub key: String,
+    /// The root manifest of the workspace.
+    pub manifest_path: Utf8PathBuf,
+    /// The key of the entry within `manifest_path`, `None` if it couldn't be
+    /// found.
+    pub manifest_span: Option<ManifestSpan>,
+}
+
+/// Why an unused dependency isn't reported as a finding.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Suppression {
+    /// A `# reves: keep` comment on the entry in the manifest.
+    KeepComment,
+    /// An `ignored` entry in `[package.metadata.reves]`.
+    Ignored,
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+pub struct SuppressedDependency {
+    #[serde(flatten)]
+    pub unused_dependency: UnusedDependency,
+    pub suppressed_by: Suppression,
+    pub reason: Option<String>,
+}
+
+/// A `[dependencies]` entry which isn't optional, but is only used when
+/// `feature` of its dependant is enabled, so it could be an optional dependency
+/// enabled by `feature`. Found by `--feature-powerset`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+pub struct OptionalCandidate {
+    #[serde(flatten)]
+    pub unused_dependency: UnusedDependency,
+    pub feature: String,
+}
+
+/// The unused dependencies found with one feature combination of
+/// `--feature-powerset`.
+#[derive(Clone, Debug)]
+pub struct FeatureCombination {
+    /// The feature flags passed to `cargo`, such as `--no-default-features
+    /// --features magenta/std`, empty for the default features.
+    pub flags: String,
+    pub(crate) features: Features,
+    pub unused_dependencies: HashSet<UnusedDependency>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub manifest_path: Utf8PathBuf,
+    /// From `[workspace.metadata.reves]`, and `[package.metadata.reves]`.
+    pub levels: Levels,
+    /// From `[package.metadata.reves]`.
+    pub ignored: Vec<IgnoredDependency>,
 }
 
+#[derive(serde::Serialize)]
 pub struct DependencyLintResults {
+    pub workspace_root: Utf8PathBuf,
+    // The workspace members which were checked.
+    pub workspace_members: BTreeMap<cargo_metadata::PackageId, WorkspaceMember>,
     // Dependencies that appear to be removable based upon the currently
     // selected features, and target.
+    #[serde(serialize_with = "serialize_sorted")]
     pub unused_dependencies: HashSet<UnusedDependency>,
-    // TODO: add information for "dependencies" that could be downgraded to
-    // being a regular "dependencies".
-    pub mismarked_dev_dependencies: (),
+    // Unused dependencies which are deliberately kept, these aren't findings.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub suppressed_dependencies: HashSet<SuppressedDependency>,
+    // `[dependencies]` entries which are only used by test-profile artifacts
+    // (unit tests, integration tests, benches, examples, and doc tests), and
+    // should be `[dev-dependencies]`. `dep_kind` is always `Normal`.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub mismarked_dev_dependencies: HashSet<UnusedDependency>,
+    // `[dependencies]` entries which are only used by the build script, and
+    // should be `[build-dependencies]`. A build script can't use
+    // `[dependencies]`, so these are also declared in `[build-dependencies]`.
+    // `dep_kind` is always `Normal`.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub mismarked_build_dependencies: HashSet<UnusedDependency>,
+    // `[dev-dependencies]` entries which a `[dependencies]` entry of the same
+    // package already provides to the tests, without adding features.
+    // `dep_kind` is always `Development`.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub redundant_dev_dependencies: HashSet<UnusedDependency>,
+    // Dependencies which aren't optional, but are only used when a feature is
+    // enabled, only found with `--feature-powerset`.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub optional_candidates: HashSet<OptionalCandidate>,
     // Artifacts that could have no dependency upon their associated crate
     // library.
+    #[serde(serialize_with = "serialize_sorted")]
     pub orphans: HashSet<OrphanArtifact>,
+    // `[workspace.dependencies]` entries which no workspace member inherits.
+    #[serde(serialize_with = "serialize_sorted")]
+    pub unused_workspace_dependencies: HashSet<UnusedWorkspaceDependency>,
+    // The levels from `[workspace.metadata.reves]`, used for findings which
+    // don't belong to a package.
+    pub workspace_levels: Levels,
+    // The manifests of every workspace member, including those which weren't
+    // checked.
+    #[serde(skip)]
+    pub member_manifest_paths: BTreeSet<Utf8PathBuf>,
+    // The artifacts of each workspace member which were checked for uses of
+    // its unused dependencies of each kind, see `artifact_description`.
+    #[serde(skip)]
+    pub checked_artifacts: BTreeMap<(cargo_metadata::PackageId, DependencyKind), BTreeSet<String>>,
+    // With `--feature-powerset`, the unused dependencies found with each
+    // feature combination, before they were combined.
+    #[serde(skip)]
+    pub feature_combinations: Vec<FeatureCombination>,
+    // Entries of the `--baseline` which no longer match a finding.
+    pub fixed_baseline_entries: Vec<BaselineEntry>,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+impl DependencyLintResults {
+    /// The name of a workspace member, falling back to the opaque package id
+    /// for packages which weren't checked.
+    pub fn package_name<'a>(&'a self, package_id: &'a cargo_metadata::PackageId) -> &'a str {
+        return match self.workspace_members.get(package_id) {
+            Some(member) => member.name.as_str(),
+            None => package_id.repr.as_str(),
+        };
+    }
+
+    /// The absolute path to the source of an orphan artifact.
+    pub fn orphan_path(&self, orphan: &OrphanArtifact) -> Option<Utf8PathBuf> {
+        let member: &WorkspaceMember = self.workspace_members.get(&orphan.crate_id)?;
+        return Some(
+            member
+                .manifest_path
+                .parent()?
+                .join(&orphan.crate_relative_path),
+        );
+    }
+
+    /// The level of a category for a package, packages which aren't workspace
+    /// members use the default levels.
+    pub fn level(&self, package_id: &cargo_metadata::PackageId, category: LintCategory) -> Level {
+        return match self.workspace_members.get(package_id) {
+            Some(member) => member.levels.level(category),
+            None => Levels::default().level(category),
+        };
+    }
+
+    pub fn unused_dependency_level(&self, unused_dep: &UnusedDependency) -> Level {
+        return self.level(
+            &unused_dep.dependant,
+            LintCategory::of_unused_dependency(unused_dep.dep_kind),
+        );
+    }
+
+    pub fn orphan_level(&self, orphan: &OrphanArtifact) -> Level {
+        return self.level(&orphan.crate_id, LintCategory::Orphans);
+    }
+
+    pub fn unused_workspace_dependency_level(&self) -> Level {
+        return self.workspace_levels.unused_workspace;
+    }
+
+    pub fn mislabeled_dependency_level(&self, mislabeled_dep: &UnusedDependency) -> Level {
+        return self.level(&mislabeled_dep.dependant, LintCategory::Mislabeled);
+    }
+
+    pub fn redundant_dev_dependency_level(&self, redundant_dep: &UnusedDependency) -> Level {
+        return self.level(&redundant_dep.dependant, LintCategory::Redundant);
+    }
+
+    pub fn optional_candidate_level(&self, optional_candidate: &OptionalCandidate) -> Level {
+        return self.level(
+            &optional_candidate.unused_dependency.dependant,
+            LintCategory::Optional,
+        );
+    }
+
+    /// The mislabeled dependencies, each with the kind of dependency it should
+    /// be, sorted.
+    pub fn mislabeled_dependencies(&self) -> Vec<(&UnusedDependency, DependencyKind)> {
+        let mut mislabeled_deps = Vec::<(&UnusedDependency, DependencyKind)>::new();
+        for mismarked_dep in self.mismarked_dev_dependencies.iter() {
+            mislabeled_deps.push((mismarked_dep, DependencyKind::Development));
+        }
+        for mismarked_dep in self.mismarked_build_dependencies.iter() {
+            mislabeled_deps.push((mismarked_dep, DependencyKind::Build));
+        }
+        mislabeled_deps.sort();
+        return mislabeled_deps;
+    }
+
+    /// Moves unused dependencies matching an `ignored` entry of their package
+    /// to the suppressed dependencies, returning the entries which didn't match
+    /// anything so stale entries can be cleaned up. Entries are matched against
+    /// the unused dependencies before any suppression, so an entry for a
+    /// dependency which also has a keep comment isn't stale.
+    pub fn remove_ignored_findings(
+        &mut self,
+    ) -> Vec<(cargo_metadata::PackageId, IgnoredDependency)> {
+        let mut matched = HashSet::<(cargo_metadata::PackageId, usize)>::new();
+        let kept_deps = self
+            .suppressed_dependencies
+            .iter()
+            .filter_map(|suppressed| {
+                return match suppressed.suppressed_by {
+                    Suppression::KeepComment => Some(&suppressed.unused_dependency),
+                    Suppression::Ignored => None,
+                };
+            });
+        for unused_dep in self.unused_dependencies.iter().chain(kept_deps) {
+            if let Some(member) = self.workspace_members.get(&unused_dep.dependant) {
+                for (i, ignored) in member.ignored.iter().enumerate() {
+                    if ignored.matches(unused_dep) {
+                        matched.insert((unused_dep.dependant.clone(), i));
+                    }
+                }
+            }
+        }
+
+        let mut unused_dependencies = HashSet::<UnusedDependency>::new();
+        for unused_dep in self.unused_dependencies.iter() {
+            let mut reason: Option<&str> = None;
+            if let Some(member) = self.workspace_members.get(&unused_dep.dependant) {
+                for ignored in member.ignored.iter() {
+                    if ignored.matches(unused_dep) {
+                        reason = Some(ignored.reason.as_str());
+                    }
+                }
+            }
+            match reason {
+                Some(reason) => {
+                    self.suppressed_dependencies.insert(SuppressedDependency {
+                        unused_dependency: unused_dep.clone(),
+                        suppressed_by: Suppression::Ignored,
+                        reason: Some(reason.to_owned()),
+                    });
+                }
+                None => {
+                    unused_dependencies.insert(unused_dep.clone());
+                }
+            }
+        }
+
+        let mut stale = Vec::<(cargo_metadata::PackageId, IgnoredDependency)>::new();
+        for (package_id, member) in self.workspace_members.iter() {
+            for (i, ignored) in member.ignored.iter().enumerate() {
+                if !matched.contains(&(package_id.clone(), i)) {
+                    stale.push((package_id.clone(), ignored.clone()));
+                }
+            }
+        }
+        self.unused_dependencies = unused_dependencies;
+        return stale;
+    }
+
+    /// Drops findings which are allowed by their package's levels.
+    pub fn remove_allowed_findings(&mut self) {
+        let unused_dependencies: HashSet<UnusedDependency> =
+            std::mem::take(&mut self.unused_dependencies);
+        self.unused_dependencies = unused_dependencies
+            .into_iter()
+            .filter(|unused_dep| {
+                return self.unused_dependency_level(unused_dep) != Level::Allow;
+            })
+            .collect();
+        let orphans: HashSet<OrphanArtifact> = std::mem::take(&mut self.orphans);
+        self.orphans = orphans
+            .into_iter()
+            .filter(|orphan| {
+                return self.orphan_level(orphan) != Level::Allow;
+            })
+            .collect();
+        if self.unused_workspace_dependency_level() == Level::Allow {
+            self.unused_workspace_dependencies.clear();
+        }
+        let mismarked_dev_dependencies: HashSet<UnusedDependency> =
+            std::mem::take(&mut self.mismarked_dev_dependencies);
+        self.mismarked_dev_dependencies = mismarked_dev_dependencies
+            .into_iter()
+            .filter(|mismarked_dep| {
+                return self.mislabeled_dependency_level(mismarked_dep) != Level::Allow;
+            })
+            .collect();
+        let mismarked_build_dependencies: HashSet<UnusedDependency> =
+            std::mem::take(&mut self.mismarked_build_dependencies);
+        self.mismarked_build_dependencies = mismarked_build_dependencies
+            .into_iter()
+            .filter(|mismarked_dep| {
+                return self.mislabeled_dependency_level(mismarked_dep) != Level::Allow;
+            })
+            .collect();
+        let redundant_dev_dependencies: HashSet<UnusedDependency> =
+            std::mem::take(&mut self.redundant_dev_dependencies);
+        self.redundant_dev_dependencies = redundant_dev_dependencies
+            .into_iter()
+            .filter(|redundant_dep| {
+                return self.redundant_dev_dependency_level(redundant_dep) != Level::Allow;
+            })
+            .collect();
+        let optional_candidates: HashSet<OptionalCandidate> =
+            std::mem::take(&mut self.optional_candidates);
+        self.optional_candidates = optional_candidates
+            .into_iter()
+            .filter(|optional_candidate| {
+                return self.optional_candidate_level(optional_candidate) != Level::Allow;
+            })
+            .collect();
+    }
+
+    /// The number of findings which fail the run.
+    pub fn denied_findings(&self) -> usize {
+        let mut count: usize = 0;
+        for unused_dep in self.unused_dependencies.iter() {
+            if self.unused_dependency_level(unused_dep) == Level::Deny {
+                count += 1;
+            }
+        }
+        for orphan in self.orphans.iter() {
+            if self.orphan_level(orphan) == Level::Deny {
+                count += 1;
+            }
+        }
+        if self.unused_workspace_dependency_level() == Level::Deny {
+            count += self.unused_workspace_dependencies.len();
+        }
+        for (mislabeled_dep, _) in self.mislabeled_dependencies().into_iter() {
+            if self.mislabeled_dependency_level(mislabeled_dep) == Level::Deny {
+                count += 1;
+            }
+        }
+        for redundant_dep in self.redundant_dev_dependencies.iter() {
+            if self.redundant_dev_dependency_level(redundant_dep) == Level::Deny {
+                count += 1;
+            }
+        }
+        for optional_candidate in self.optional_candidates.iter() {
+            if self.optional_candidate_level(optional_candidate) == Level::Deny {
+                count += 1;
+            }
+        }
+        return count;
+    }
+}
+
+/// `HashSet` iteration order is unstable, so sort before serializing to keep
+/// the output reproducible.
+fn serialize_sorted<T, S>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Ord + serde::Serialize,
+    S: serde::Serializer,
+{
+    let mut sorted: Vec<&T> = set.iter().collect();
+    sorted.sort();
+    return serializer.collect_seq(sorted);
+}
+
+#[derive(
+    Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum OrphanArtifactKind {
     Bench,
     Binary,
@@ -183,7 +634,7 @@ fn kind_to_artifact_kind(kind_strings: &[String]) -> anyhow::Result<ArtifactKind
     return flattened_artifact_kind.context("missing artifact kind");
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
 pub struct OrphanArtifact {
     pub crate_id: cargo_metadata::PackageId,
     pub kind: OrphanArtifactKind,
@@ -207,12 +658,49 @@ enum CargoInvocationKind {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 enum Features {
-    Specified(Vec<String>),
+    Specified {
+        features: Vec<String>,
+        default_features: bool,
+    },
     Default,
     All,
 }
 
+/// The features `lint_dependencies` checks the workspace with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FeatureSelection {
+    /// `--all-features`, the default.
+    All,
+    /// `--features`, and `--no-default-features`.
+    Specified {
+        features: Vec<String>,
+        default_features: bool,
+    },
+    /// `--feature-powerset`, checks each combination of at most `depth`
+    /// features of each checked workspace member, with the other members at
+    /// their default features, see `feature_powerset_combinations`.
+    Powerset { depth: Option<usize> },
+}
+
+impl FeatureSelection {
+    /// The features of a single check, `--feature-powerset` checks, and
+    /// `--fix` verifies each of its combinations instead.
+    fn checked_features(&self) -> Features {
+        return match self {
+            FeatureSelection::All | FeatureSelection::Powerset { .. } => Features::All,
+            FeatureSelection::Specified {
+                features,
+                default_features,
+            } => Features::Specified {
+                features: features.clone(),
+                default_features: *default_features,
+            },
+        };
+    }
+}
+
 fn parse_cargo_version_output(output: &str) -> anyhow::Result<semver::Version> {
     let release_regex = Regex::new("^release:(.*)$").unwrap();
     let mut release: Option<semver::Version> = None;
@@ -272,6 +760,47 @@ fn workspace_members(
     };
 }
 
+fn checked_workspace_members(
+    structured_metadata: &StructuredMetadata,
+    members: WorkspaceMembers,
+) -> anyhow::Result<BTreeMap<cargo_metadata::PackageId, WorkspaceMember>> {
+    let mut checked_members = BTreeMap::<cargo_metadata::PackageId, WorkspaceMember>::new();
+    for package_id in workspace_members(structured_metadata, members).iter() {
+        let package: &cargo_metadata::Package = &structured_metadata.packages[package_id];
+        let package_config: config::Config = config::Config::from_metadata(&package.metadata)
+            .with_context(|| format!("In package {}", package.name))?;
+        anyhow::ensure!(
+            package_config.level(LintCategory::UnusedWorkspace).is_none(),
+            "unused-workspace must be set in [workspace.metadata.reves], not [package.metadata.reves] of {}",
+            package.name
+        );
+        checked_members.insert(
+            package_id.clone(),
+            WorkspaceMember {
+                name: package.name.clone(),
+                manifest_path: package.manifest_path.clone(),
+                levels: Levels::resolve(&structured_metadata.workspace_config, &package_config),
+                ignored: package_config.ignored,
+            },
+        );
+    }
+    return Ok(checked_members);
+}
+
+/// The manifests of all workspace members, see
+/// `DependencyLintResults::member_manifest_paths`.
+fn member_manifest_paths(structured_metadata: &StructuredMetadata) -> BTreeSet<Utf8PathBuf> {
+    let mut manifest_paths = BTreeSet::<Utf8PathBuf>::new();
+    for package_id in structured_metadata.all_workspace_members.iter() {
+        manifest_paths.insert(
+            structured_metadata.packages[package_id]
+                .manifest_path
+                .clone(),
+        );
+    }
+    return manifest_paths;
+}
+
 fn toml_key_to_dep_kind(key: &str) -> Option<DependencyKind> {
     return match key {
         "dependencies" => Some(DependencyKind::Normal),
@@ -309,6 +838,8 @@ struct StructuredMetadata {
     all_workspace_members: HashSet<cargo_metadata::PackageId>,
     default_workspace_members: Option<HashSet<cargo_metadata::PackageId>>,
     crate_links: BTreeMap<String, cargo_metadata::PackageId>,
+    workspace_root: Utf8PathBuf,
+    workspace_config: config::Config,
 }
 
 fn metadata_to_structured_metadata(
@@ -379,12 +910,21 @@ fn metadata_to_structured_metadata(
         }
     }
 
+    let workspace_config: config::Config =
+        config::Config::from_metadata(&metadata.workspace_metadata).context("In the workspace")?;
+    anyhow::ensure!(
+        workspace_config.ignored.is_empty(),
+        "Ignored dependencies must be listed in [package.metadata.reves], not [workspace.metadata.reves]"
+    );
+
     return Ok(StructuredMetadata {
         nodes,
         packages,
         all_workspace_members,
         default_workspace_members,
         crate_links,
+        workspace_root: metadata.workspace_root.clone(),
+        workspace_config,
     });
 }
 
@@ -425,20 +965,104 @@ fn find_node_dep<'a>(
         .context(format!("Missing crate {} in NodeDep list", krate.name));
 }
 
-/*
-  Definitely suboptimal, and we could transform the `Vec` into a `HashMap` at a
-  higher level, but this should be fine in most set ups.
-*/
+/// Finds the declaration of the dependency on version `version` of `krate`,
+/// which code refers to as `renamed_crate`. A package may depend on several
+/// versions of the same crate (such as `rand07 = { package = "rand", version =
+/// "0.7" }` alongside `rand = "0.8"`), so the rename, and then the version
+/// requirement, disambiguate between declarations.
 fn find_package_dependency<'a>(
-    krate: UnrenamedCrate<'a>,
+    krate: UnrenamedCrate<'_>,
+    renamed_crate: RenamedCrate<'_>,
+    version: &semver::Version,
     deps: &'a [cargo_metadata::Dependency],
 ) -> anyhow::Result<&'a cargo_metadata::Dependency> {
+    /*
+      Definitely suboptimal, and we could transform the `Vec` into a `HashMap` at a
+      higher level, but this should be fine in most set ups.
+    */
+    let mut unrenamed_deps = Vec::<&cargo_metadata::Dependency>::new();
     for dep in deps.iter() {
-        if dep.name == krate.name {
+        if dep.name != krate.name {
+            continue;
+        }
+        match dep.rename.as_ref() {
+            Some(rename) => {
+                if rename.replace('-', "_") == renamed_crate.name {
+                    return Ok(dep);
+                }
+            }
+            None => {
+                unrenamed_deps.push(dep);
+            }
+        }
+    }
+    for dep in unrenamed_deps.iter() {
+        if dep.req.matches(version) {
             return Ok(dep);
         }
     }
-    anyhow::bail!("Missing crate in Dependency list");
+    // Such as pre-releases, which only match requirements naming a pre-release.
+    return unrenamed_deps.first().copied().with_context(|| {
+        return format!(
+            "Missing crate {} {} in Dependency list",
+            krate.name, version
+        );
+    });
+}
+
+/// The key of `dep` within its dependency table, see
+/// `UnusedDependency::manifest_key`.
+fn manifest_key(dep: &cargo_metadata::Dependency) -> &str {
+    return match dep.rename.as_ref() {
+        Some(rename) => rename.as_str(),
+        None => dep.name.as_str(),
+    };
+}
+
+/// The platforms of the tables declaring the `dep_kind` dependency with the
+/// manifest key `key` which are active for `target_platform`, see
+/// `UnusedDependency::targets`.
+fn dependency_targets(
+    deps: &[cargo_metadata::Dependency],
+    key: &str,
+    dep_kind: DependencyKind,
+    target_platform: &platform::TargetPlatform,
+) -> Vec<Option<String>> {
+    let mut targets = Vec::<Option<String>>::new();
+    for dep in deps.iter() {
+        let is_dep_kind: bool = match dep.kind {
+            cargo_metadata::DependencyKind::Normal => dep_kind == DependencyKind::Normal,
+            cargo_metadata::DependencyKind::Development => dep_kind == DependencyKind::Development,
+            cargo_metadata::DependencyKind::Build => dep_kind == DependencyKind::Build,
+            _ => false,
+        };
+        if manifest_key(dep) == key && is_dep_kind && target_platform.matches(dep.target.as_ref()) {
+            targets.push(dep.target.as_ref().map(|target| {
+                return target.to_string();
+            }));
+        }
+    }
+    targets.sort();
+    targets.dedup();
+    return targets;
+}
+
+/// Describes an artifact as its kinds, and name, such as "bin `orphan_bin`",
+/// or "lib `magenta` (test)" for its unit tests.
+fn artifact_description(artifact: &cargo_metadata::Artifact) -> String {
+    let mut description: String = format!(
+        "{} `{}`",
+        artifact.target.kind.join(", "),
+        artifact.target.name
+    );
+    let is_lib_or_bin: bool = matches!(
+        kind_to_artifact_kind(&artifact.target.kind),
+        Ok(ArtifactKind::Library | ArtifactKind::Binary)
+    );
+    if artifact.profile.test && is_lib_or_bin {
+        description.push_str(" (test)");
+    }
+    return description;
 }
 
 fn has_lib_artifact(artifacts: &[cargo_metadata::Target]) -> anyhow::Result<bool> {
@@ -451,6 +1075,16 @@ fn has_lib_artifact(artifacts: &[cargo_metadata::Target]) -> anyhow::Result<bool
 }
 
 fn compute_cargo_args(cargo_args: &CargoArgs) -> Vec<Cow<'_, OsStr>> {
+    let mut args: Vec<Cow<'_, OsStr>> = compute_global_cargo_args(cargo_args);
+    if cargo_args.workspace {
+        args.push(Cow::Borrowed(OsStr::new("--workspace")));
+    }
+    return args;
+}
+
+/// The arguments of `cargo_args` other than the package selection, for
+/// commands run on a single package.
+fn compute_global_cargo_args(cargo_args: &CargoArgs) -> Vec<Cow<'_, OsStr>> {
     let mut args = Vec::<Cow<'static, OsStr>>::new();
     args.push(Cow::Borrowed(OsStr::new("--color")));
     args.push(Cow::Owned(OsString::from(format!("{}", cargo_args.color))));
@@ -463,9 +1097,6 @@ fn compute_cargo_args(cargo_args: &CargoArgs) -> Vec<Cow<'_, OsStr>> {
     if cargo_args.offline {
         args.push(Cow::Borrowed(OsStr::new("--offline")));
     }
-    if cargo_args.workspace {
-        args.push(Cow::Borrowed(OsStr::new("--workspace")));
-    }
     for config in cargo_args.config.iter() {
         args.push(Cow::Borrowed(OsStr::new("--config")));
         args.push(Cow::Borrowed(OsStr::new(config.as_str())));
@@ -484,28 +1115,34 @@ fn compute_cargo_args(cargo_args: &CargoArgs) -> Vec<Cow<'_, OsStr>> {
 fn compute_feature_args(features: &Features) -> Vec<Cow<'static, OsStr>> {
     let mut args = Vec::<Cow<'static, OsStr>>::new();
     match features {
-        Features::Specified(features) => {
-            args.push(Cow::Borrowed(OsStr::new("--features")));
-
-            let mut features_len: usize = 0;
-            /* +1, and -1 for commas */
-            for feature in features.iter() {
-                features_len += feature.len() + 1;
-            }
-            if features_len > 0 {
-                features_len -= 1;
+        Features::Specified {
+            features,
+            default_features,
+        } => {
+            if !default_features {
+                args.push(Cow::Borrowed(OsStr::new("--no-default-features")));
             }
+            if !features.is_empty() {
+                let mut features_len: usize = 0;
+                /* +1, and -1 for commas */
+                for feature in features.iter() {
+                    features_len += feature.len() + 1;
+                }
+                if features_len > 0 {
+                    features_len -= 1;
+                }
 
-            let mut joined_features = OsString::with_capacity(features_len);
-            for i in 0..features.len() {
-                if i != 0 {
-                    joined_features.push(OsStr::new(","));
+                let mut joined_features = OsString::with_capacity(features_len);
+                for i in 0..features.len() {
+                    if i != 0 {
+                        joined_features.push(OsStr::new(","));
+                    }
+                    joined_features.push(OsStr::new(features[i].as_str()));
                 }
-                joined_features.push(OsStr::new(features[i].as_str()));
-            }
 
-            args.push(Cow::Borrowed(OsStr::new("--features")));
-            args.push(Cow::Owned(joined_features));
+                args.push(Cow::Borrowed(OsStr::new("--features")));
+                args.push(Cow::Owned(joined_features));
+            }
         }
         Features::Default => { /* do nothing */ }
         Features::All => {
@@ -544,63 +1181,62 @@ fn compute_encoded_flags(flags: &[&str]) -> String {
     return flag_string;
 }
 
-fn find_unused_dependencies_doc(
-    workspace: &Path,
-    check_target: &CheckTarget,
+/// The arguments, and environment variables of a `cargo` command.
+struct CargoCommand<'a> {
+    args: Vec<Cow<'a, OsStr>>,
+    env: HashMap<Cow<'static, OsStr>, Cow<'static, OsStr>>,
+}
+
+/// The `cargo check` whose artifacts are checked for unused dependencies, also
+/// run by `workspace_builds` so `--fix` is verified with the same
+/// configuration (and target directory).
+fn check_command<'a>(
+    check_target: &'a CheckTarget,
     features: &Features,
-    structured_metadata: &StructuredMetadata,
-    cargo_args: &CargoArgs,
-) -> anyhow::Result<HashSet<UnusedDependency>> {
-    let mut unused_deps = HashSet::<UnusedDependency>::new();
+    cargo_args: &'a CargoArgs,
+) -> CargoCommand<'a> {
+    let mut args = Vec::<Cow<'a, OsStr>>::new();
+    let mut env = HashMap::<Cow<'static, OsStr>, Cow<'static, OsStr>>::new();
 
-    let mut args = Vec::<Cow<'static, OsStr>>::new();
-    let mut rustdoctest_args = Vec::<Cow<'static, OsStr>>::new();
+    args.push(Cow::Borrowed(OsStr::new("check")));
+    args.append(&mut compute_cargo_args(cargo_args));
+    args.push(Cow::Borrowed(OsStr::new("--all-targets")));
+    args.push(Cow::Borrowed(OsStr::new("--target-dir=target_reves")));
+
+    args.append(&mut compute_target_args(check_target));
+    args.append(&mut compute_feature_args(features));
+
+    env.insert(
+        Cow::Borrowed(OsStr::new("CARGO_ENCODED_RUSTFLAGS")),
+        Cow::Owned(OsString::from(compute_encoded_flags(&[
+            "--warn=unused-crate-dependencies",
+        ]))),
+    );
+    return CargoCommand { args, env };
+}
+
+/// The `cargo test --doc` whose doc tests are checked for unused dependencies,
+/// run with `-p` for each package with a library, followed by `--` and
+/// `DOC_TEST_ARGS`. Also run by `workspace_builds`.
+fn doc_test_command<'a>(
+    check_target: &'a CheckTarget,
+    features: &Features,
+    cargo_args: &'a CargoArgs,
+) -> CargoCommand<'a> {
+    let mut args = Vec::<Cow<'a, OsStr>>::new();
     let mut env = HashMap::<Cow<'static, OsStr>, Cow<'static, OsStr>>::new();
 
     args.push(Cow::Borrowed(OsStr::new("test")));
-    //args.append(&mut compute_cargo_args(cargo_args));
-    {
-        args.push(Cow::Borrowed(OsStr::new("--color")));
-        args.push(Cow::Owned(OsString::from(format!("{}", cargo_args.color))));
-        if cargo_args.frozen {
-            args.push(Cow::Borrowed(OsStr::new("--frozen")));
-        }
-        if cargo_args.locked {
-            args.push(Cow::Borrowed(OsStr::new("--locked")));
-        }
-        if cargo_args.offline {
-            args.push(Cow::Borrowed(OsStr::new("--offline")));
-        }
-        // TODO fix
-        /*if cargo_args.workspace {
-            args.push(Cow::Borrowed(OsStr::new("--workspace")));
-        }*/
-        for config in cargo_args.config.iter() {
-            args.push(Cow::Borrowed(OsStr::new("--config")));
-            args.push(Cow::Borrowed(OsStr::new(config.as_str())));
-        }
-        if let Some(target_dir) = cargo_args.target_dir.as_ref() {
-            args.push(Cow::Borrowed(OsStr::new("--target-dir")));
-            args.push(Cow::Borrowed(target_dir.as_os_str()));
-        }
-        if let Some(manifest_path) = cargo_args.manifest_path.as_ref() {
-            args.push(Cow::Borrowed(OsStr::new("--manifest-path")));
-            args.push(Cow::Borrowed(manifest_path.as_os_str()));
-        }
-    }
+    // Not `compute_cargo_args`, as `--workspace` selects packages without doc
+    // tests.
+    args.append(&mut compute_global_cargo_args(cargo_args));
     args.push(Cow::Borrowed(OsStr::new("--quiet")));
     args.push(Cow::Borrowed(OsStr::new("--doc")));
     args.push(Cow::Borrowed(OsStr::new("--target-dir=target_reves_doc")));
-    args.push(Cow::Borrowed(OsStr::new("--message-format=json")));
 
     args.append(&mut compute_target_args(check_target));
     args.append(&mut compute_feature_args(features));
 
-    // --json=unused-externs-silent only works if running all tests (including
-    // ignored). If this causes the crate to not compile then `ignored` should be
-    // switched to `text`.
-    rustdoctest_args.push(Cow::Borrowed(OsStr::new("--include-ignored")));
-
     // TODO: remove. Just for testing purposes
     env.insert(
         Cow::Borrowed(OsStr::new("RUSTC_BOOTSTRAP")),
@@ -617,12 +1253,33 @@ fn find_unused_dependencies_doc(
             "unstable-options",
         ]))),
     );
+    return CargoCommand { args, env };
+}
+
+/*
+  --json=unused-externs-silent only works if running all tests (including
+  ignored). If this causes the crate to not compile then `ignored` should be
+  switched to `text`.
+*/
+const DOC_TEST_ARGS: [&str; 1] = ["--include-ignored"];
+
+fn find_unused_dependencies_doc(
+    workspace: &Path,
+    check_target: &CheckTarget,
+    features: &Features,
+    structured_metadata: &StructuredMetadata,
+    cargo_args: &CargoArgs,
+) -> anyhow::Result<HashSet<UnusedDependency>> {
+    let mut unused_deps = HashSet::<UnusedDependency>::new();
+
+    let CargoCommand { mut args, env } = doc_test_command(check_target, features, cargo_args);
+    args.push(Cow::Borrowed(OsStr::new("--message-format=json")));
 
     for package_id in workspace_members(
         structured_metadata,
         WorkspaceMembers::from_workspace_arg(cargo_args.workspace),
     ) {
-        if !has_lib_artifact(structured_metadata.packages[&package_id].targets.as_slice())? {
+        if !has_lib_artifact(structured_metadata.packages[package_id].targets.as_slice())? {
             // Skip, only "lib"s have doc tests.
             continue;
         }
@@ -630,12 +1287,9 @@ fn find_unused_dependencies_doc(
         let output: std::process::Output = Command::new(cargo_command())
             .current_dir(workspace)
             .args(&args)
-            .args([
-                "-p",
-                structured_metadata.packages[&package_id].name.as_str(),
-            ])
+            .args(["-p", structured_metadata.packages[package_id].name.as_str()])
             .arg("--")
-            .args(&rustdoctest_args)
+            .args(DOC_TEST_ARGS)
             .stdin(std::process::Stdio::null())
             .stdout(std::process::Stdio::null())
             .envs(env.clone())
@@ -651,7 +1305,7 @@ fn find_unused_dependencies_doc(
             };
             match find_node_dep(
                 renamed_crate.clone(),
-                &structured_metadata.nodes[&package_id],
+                &structured_metadata.nodes[package_id],
             ) {
                 Ok(node_dep) => {
                     let krate: UnrenamedCrateOwned = UnrenamedCrateOwned {
@@ -661,7 +1315,9 @@ fn find_unused_dependencies_doc(
                     };
                     let dependency: &cargo_metadata::Dependency = find_package_dependency(
                         krate,
-                        structured_metadata.packages[&package_id]
+                        renamed_crate.clone(),
+                        &structured_metadata.packages[&node_dep.pkg].version,
+                        structured_metadata.packages[package_id]
                             .dependencies
                             .as_slice(),
                     )?;
@@ -674,9 +1330,12 @@ fn find_unused_dependencies_doc(
                             dependency_name: UnrenamedCrateOwned {
                                 name: Cow::Owned(dependency.name.clone()),
                             },
-                            dependant_manifest_path: structured_metadata.packages[&package_id]
+                            manifest_key: manifest_key(dependency).to_owned(),
+                            dependant_manifest_path: structured_metadata.packages[package_id]
                                 .manifest_path
                                 .clone(),
+                            targets: Vec::new(),
+                            manifest_span: None,
                         };
                         let is_new: bool = unused_deps.insert(unused_dep.clone());
                         assert!(is_new, "{:#?}", unused_dep);
@@ -691,7 +1350,7 @@ fn find_unused_dependencies_doc(
                       the target name isn't the same as the crate name).
                     */
                     if renamed_crate.name
-                        != structured_metadata.packages[&package_id]
+                        != structured_metadata.packages[package_id]
                             .name
                             .replace('-', "_")
                             .as_str()
@@ -722,6 +1381,76 @@ pub struct CargoArgs {
     pub manifest_path: Option<PathBuf>,
 }
 
+/// The names of the checked workspace members with doc tests, as `cargo test
+/// --doc` fails for packages without a library.
+fn doc_test_packages(workspace: &Path, cargo_args: &CargoArgs) -> anyhow::Result<Vec<String>> {
+    let cargo_version: semver::Version = cargo_version(workspace)?;
+    let metadata: cargo_metadata::Metadata = cargo_metadata::MetadataCommand::new()
+        .current_dir(workspace)
+        .features(cargo_metadata::CargoOpt::AllFeatures)
+        .exec()?;
+    let structured_metadata: StructuredMetadata =
+        metadata_to_structured_metadata(&metadata, &cargo_version)?;
+    let mut package_names = Vec::<String>::new();
+    for package_id in workspace_members(
+        &structured_metadata,
+        WorkspaceMembers::from_workspace_arg(cargo_args.workspace),
+    ) {
+        let package: &cargo_metadata::Package = &structured_metadata.packages[package_id];
+        if has_lib_artifact(package.targets.as_slice())? {
+            package_names.push(package.name.clone());
+        }
+    }
+    package_names.sort();
+    return Ok(package_names);
+}
+
+/// Whether the workspace still builds after `--fix` with each of the checked
+/// `features`, with the artifacts, and the doc tests of `doc_test_packages`
+/// checked for unused dependencies. Runs the same commands as the checks, so
+/// only the edited packages are rebuilt.
+fn workspace_builds(
+    workspace: &Path,
+    check_target: &CheckTarget,
+    checked_features: &[Features],
+    doc_test_packages: &[String],
+    cargo_args: &CargoArgs,
+) -> anyhow::Result<bool> {
+    for features in checked_features.iter() {
+        let CargoCommand { args, env } = check_command(check_target, features, cargo_args);
+        let status = Command::new(cargo_command())
+            .current_dir(workspace)
+            .args(&args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .envs(env)
+            .status()?;
+        if !status.success() {
+            return Ok(false);
+        }
+
+        let CargoCommand { args, env } = doc_test_command(check_target, features, cargo_args);
+        for package_name in doc_test_packages.iter() {
+            let status = Command::new(cargo_command())
+                .current_dir(workspace)
+                .args(&args)
+                .args(["-p", package_name.as_str()])
+                .arg("--")
+                .args(DOC_TEST_ARGS)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .envs(env.clone())
+                .status()?;
+            if !status.success() {
+                return Ok(false);
+            }
+        }
+    }
+    return Ok(true);
+}
+
 fn find_unused_dependencies_check(
     workspace: &Path,
     check_target: &CheckTarget,
@@ -735,25 +1464,9 @@ fn find_unused_dependencies_check(
     let mut all_link_deps = HashSet::<UsedLinkDependency>::new();
     let mut orphans = HashSet::<OrphanArtifact>::new();
 
-    let mut args = Vec::<Cow<'static, OsStr>>::new();
-    let mut env = HashMap::<Cow<'static, OsStr>, Cow<'static, OsStr>>::new();
-
-    args.push(Cow::Borrowed(OsStr::new("check")));
-    args.append(&mut compute_cargo_args(cargo_args));
-    args.push(Cow::Borrowed(OsStr::new("--all-targets")));
-    args.push(Cow::Borrowed(OsStr::new("--target-dir=target_reves")));
+    let CargoCommand { mut args, env } = check_command(check_target, features, cargo_args);
     args.push(Cow::Borrowed(OsStr::new("--message-format=json")));
 
-    args.append(&mut compute_target_args(check_target));
-    args.append(&mut compute_feature_args(features));
-
-    env.insert(
-        Cow::Borrowed(OsStr::new("CARGO_ENCODED_RUSTFLAGS")),
-        Cow::Owned(OsString::from(compute_encoded_flags(&[
-            "--warn=unused-crate-dependencies",
-        ]))),
-    );
-
     let status = Command::new(cargo_command())
         .current_dir(workspace)
         .args(&args)
@@ -813,92 +1526,93 @@ fn find_unused_dependencies_check(
                     assert!(active_unused_deps.is_empty());
                 }
             }
-            cargo_metadata::Message::CompilerMessage(message) => {
+            cargo_metadata::Message::CompilerMessage(message)
                 if structured_metadata
                     .all_workspace_members
-                    .contains(&message.package_id)
-                {
-                    if let Some(message_artifact) = active_message_artifact.as_ref() {
-                        assert!(*message_artifact == message.target);
-                    } else {
-                        active_message_artifact = Some(message.target.clone());
-                    }
-                    if let Some(diagnostic_code) = &message.message.code {
-                        if diagnostic_code.code.as_str() == "unused_crate_dependencies" {
-                            let renamed_crate: RenamedCrateOwned =
-                                parse_unused_crate_diagnostic(message.message.message.as_str())?;
-                            match find_node_dep(
-                                renamed_crate.as_unowned(),
-                                &structured_metadata.nodes[&message.package_id],
-                            ) {
-                                Ok(node_dep) => {
-                                    let krate: UnrenamedCrateOwned = UnrenamedCrateOwned {
-                                        name: Cow::Owned(
-                                            structured_metadata.packages[&node_dep.pkg]
-                                                .name
-                                                .to_owned(),
-                                        ),
+                    .contains(&message.package_id) =>
+            {
+                if let Some(message_artifact) = active_message_artifact.as_ref() {
+                    assert!(*message_artifact == message.target);
+                } else {
+                    active_message_artifact = Some(message.target.clone());
+                }
+                if let Some(diagnostic_code) = &message.message.code {
+                    if diagnostic_code.code.as_str() == "unused_crate_dependencies" {
+                        let renamed_crate: RenamedCrateOwned =
+                            parse_unused_crate_diagnostic(message.message.message.as_str())?;
+                        match find_node_dep(
+                            renamed_crate.as_unowned(),
+                            &structured_metadata.nodes[&message.package_id],
+                        ) {
+                            Ok(node_dep) => {
+                                let krate: UnrenamedCrateOwned = UnrenamedCrateOwned {
+                                    name: Cow::Owned(
+                                        structured_metadata.packages[&node_dep.pkg].name.to_owned(),
+                                    ),
+                                };
+                                let dependency: &cargo_metadata::Dependency =
+                                    find_package_dependency(
+                                        krate,
+                                        renamed_crate.as_unowned(),
+                                        &structured_metadata.packages[&node_dep.pkg].version,
+                                        structured_metadata.packages[&message.package_id]
+                                            .dependencies
+                                            .as_slice(),
+                                    )?;
+                                for dep_kind in dependency_kinds(node_dep)?.into_iter() {
+                                    let unused_dep = UnusedDependency {
+                                        dependant: message.package_id.clone(),
+                                        dependency: node_dep.pkg.clone(),
+                                        dep_kind,
+
+                                        dependency_name: UnrenamedCrateOwned {
+                                            name: Cow::Owned(dependency.name.clone()),
+                                        },
+                                        manifest_key: manifest_key(dependency).to_owned(),
+                                        dependant_manifest_path: structured_metadata.packages
+                                            [&message.package_id]
+                                            .manifest_path
+                                            .clone(),
+                                        targets: Vec::new(),
+                                        manifest_span: None,
                                     };
-                                    let dependency: &cargo_metadata::Dependency =
-                                        find_package_dependency(
-                                            krate,
-                                            structured_metadata.packages[&message.package_id]
-                                                .dependencies
-                                                .as_slice(),
-                                        )?;
-                                    for dep_kind in dependency_kinds(node_dep)?.into_iter() {
-                                        let unused_dep = UnusedDependency {
-                                            dependant: message.package_id.clone(),
-                                            dependency: node_dep.pkg.clone(),
-                                            dep_kind,
-
-                                            dependency_name: UnrenamedCrateOwned {
-                                                name: Cow::Owned(dependency.name.clone()),
-                                            },
-                                            dependant_manifest_path: structured_metadata.packages
-                                                [&message.package_id]
-                                                .manifest_path
-                                                .clone(),
-                                        };
-                                        active_unused_deps.push(unused_dep);
-                                    }
+                                    active_unused_deps.push(unused_dep);
                                 }
-                                Err(e) => {
-                                    /*
-                                      A crate can't rename itself so they should be equivalent other
-                                      than regular name normalization if the crate is referring to
-                                      itself as a library (note that the crate binaries, and
-                                      examples may have different names than the crate library so
-                                      the artifact name isn't the same as the crate name).
-                                    */
-                                    if <Cow<'_, str> as Borrow<str>>::borrow(&renamed_crate.name)
-                                        != structured_metadata.packages[&message.package_id]
-                                            .name
-                                            .replace('-', "_")
-                                            .as_str()
-                                    {
-                                        return Err(e);
-                                    } else {
-                                        orphans.insert(OrphanArtifact {
-                                            crate_id: message.package_id.clone(),
-                                            kind: OrphanArtifactKind::try_from(
-                                                kind_to_artifact_kind(&message.target.kind)?,
-                                            )?,
-                                            artifact_name: message.target.name.clone(),
-                                            crate_relative_path: message
-                                                .target
-                                                .src_path
-                                                .strip_prefix(
-                                                    structured_metadata.packages
-                                                        [&message.package_id]
-                                                        .manifest_path
-                                                        .parent()
-                                                        .unwrap(),
-                                                )
-                                                .unwrap()
-                                                .to_owned(),
-                                        });
-                                    }
+                            }
+                            Err(e) => {
+                                /*
+                                  A crate can't rename itself so they should be equivalent other
+                                  than regular name normalization if the crate is referring to
+                                  itself as a library (note that the crate binaries, and
+                                  examples may have different names than the crate library so
+                                  the artifact name isn't the same as the crate name).
+                                */
+                                if <Cow<'_, str> as Borrow<str>>::borrow(&renamed_crate.name)
+                                    != structured_metadata.packages[&message.package_id]
+                                        .name
+                                        .replace('-', "_")
+                                        .as_str()
+                                {
+                                    return Err(e);
+                                } else {
+                                    orphans.insert(OrphanArtifact {
+                                        crate_id: message.package_id.clone(),
+                                        kind: OrphanArtifactKind::try_from(kind_to_artifact_kind(
+                                            &message.target.kind,
+                                        )?)?,
+                                        artifact_name: message.target.name.clone(),
+                                        crate_relative_path: message
+                                            .target
+                                            .src_path
+                                            .strip_prefix(
+                                                structured_metadata.packages[&message.package_id]
+                                                    .manifest_path
+                                                    .parent()
+                                                    .unwrap(),
+                                            )
+                                            .unwrap()
+                                            .to_owned(),
+                                    });
                                 }
                             }
                         }
@@ -969,6 +1683,11 @@ fn find_unused_dependencies_check(
     // UnusedDependency is true for all artifacts built that may have been able to
     // use it.
     let mut unused_deps_squashed = HashSet::<UnusedDependency>::new();
+    // Normal dependencies unused by every non-test artifact, whether or not the
+    // tests use them.
+    let mut test_only_deps = HashSet::<UnusedDependency>::new();
+    let mut checked_artifacts =
+        BTreeMap::<(cargo_metadata::PackageId, DependencyKind), BTreeSet<String>>::new();
     for (unused_dep, artifacts) in unused_deps.iter() {
         let mut possible_users = HashSet::<cargo_metadata::Artifact>::new();
 
@@ -1015,21 +1734,249 @@ fn find_unused_dependencies_check(
             // checking somewhere.
             //unreachable!();
         }
+        if unused_dep.dep_kind == DependencyKind::Normal {
+            // The artifacts of the package itself, rather than of its tests.
+            let mut non_test_users = HashSet::<&cargo_metadata::Artifact>::new();
+            for artifact in possible_users.iter() {
+                let is_lib_or_bin: bool = matches!(
+                    kind_to_artifact_kind(&artifact.target.kind)?,
+                    ArtifactKind::Library | ArtifactKind::Binary
+                );
+                if is_lib_or_bin && !artifact.profile.test {
+                    non_test_users.insert(artifact);
+                }
+            }
+            let is_test_only: bool = !non_test_users.is_empty()
+                && non_test_users.iter().all(|artifact| {
+                    return artifacts.contains(*artifact);
+                });
+            if is_test_only {
+                test_only_deps.insert(unused_dep.clone());
+            }
+        }
         if possible_users.difference(artifacts).next().is_none() {
             unused_deps_squashed.insert(unused_dep.clone());
+            let descriptions: &mut BTreeSet<String> = checked_artifacts
+                .entry((unused_dep.dependant.clone(), unused_dep.dep_kind))
+                .or_default();
+            for artifact in possible_users.iter() {
+                descriptions.insert(artifact_description(artifact));
+            }
+        }
+    }
+
+    // Normal dependencies unused by every artifact, which the build script uses.
+    // The build script artifact tells whether it uses the package, whichever
+    // entry (of any key, or platform) it was compiled with, so `--fix` inserts
+    // the spec of the normal entry under `[build-dependencies]` if needed.
+    let mut build_only_deps = HashSet::<UnusedDependency>::new();
+    for unused_dep in unused_deps_squashed.iter() {
+        if unused_dep.dep_kind != DependencyKind::Normal {
+            continue;
+        }
+        let mut build_scripts = Vec::<&cargo_metadata::Artifact>::new();
+        for artifact in package_artifacts[&unused_dep.dependant].iter() {
+            if kind_to_artifact_kind(&artifact.target.kind)? == ArtifactKind::BuildScript {
+                build_scripts.push(artifact);
+            }
+        }
+        let is_built_with: bool = structured_metadata.nodes[&unused_dep.dependant]
+            .deps
+            .values()
+            .any(|node_dep| {
+                return node_dep.pkg == unused_dep.dependency
+                    && node_dep.dep_kinds.iter().any(|dep_kind_info| {
+                        return dep_kind_info.kind == cargo_metadata::DependencyKind::Build;
+                    });
+            });
+        if build_scripts.is_empty() || !is_built_with {
+            continue;
+        }
+        // A build script uses the package unless it reported it as unused.
+        let is_used_by_build_script: bool = build_scripts.iter().any(|build_script| {
+            return !unused_deps.iter().any(|(build_dep, artifacts)| {
+                return build_dep.dep_kind == DependencyKind::Build
+                    && build_dep.dependant == unused_dep.dependant
+                    && build_dep.dependency == unused_dep.dependency
+                    && artifacts.contains(*build_script);
+            });
+        });
+        if is_used_by_build_script {
+            build_only_deps.insert(unused_dep.clone());
         }
     }
 
     return Ok(DependencyLintResults {
+        workspace_root: structured_metadata.workspace_root.clone(),
+        workspace_members: checked_workspace_members(
+            structured_metadata,
+            WorkspaceMembers::from_workspace_arg(cargo_args.workspace),
+        )?,
         unused_dependencies: unused_deps_squashed,
-        mismarked_dev_dependencies: (),
+        suppressed_dependencies: HashSet::new(),
+        mismarked_dev_dependencies: test_only_deps,
+        mismarked_build_dependencies: build_only_deps,
+        redundant_dev_dependencies: HashSet::new(),
+        optional_candidates: HashSet::new(),
         orphans,
+        unused_workspace_dependencies: HashSet::new(),
+        workspace_levels: Levels::resolve(
+            &structured_metadata.workspace_config,
+            &config::Config::default(),
+        ),
+        member_manifest_paths: member_manifest_paths(structured_metadata),
+        checked_artifacts,
+        feature_combinations: Vec::new(),
+        fixed_baseline_entries: Vec::new(),
     });
 }
 
+/// Whether the `normal` entry provides everything the `dev` entry of the same
+/// package does: it has the same requirement, isn't optional, applies on every
+/// platform `dev` does, and enables at least the features of `dev`. Cargo makes
+/// `[dependencies]` available to the tests, so `dev` is then redundant, unlike
+/// a `dev` entry which adds features for the tests.
+fn is_subsumed_by(dev: &cargo_metadata::Dependency, normal: &cargo_metadata::Dependency) -> bool {
+    return normal.req == dev.req
+        && !normal.optional
+        && (normal.target.is_none() || normal.target == dev.target)
+        && (normal.uses_default_features || !dev.uses_default_features)
+        && dev.features.iter().all(|feature| {
+            return normal.features.contains(feature);
+        });
+}
+
+/// The `[dev-dependencies]` entries of `workspace_members` which are subsumed
+/// by a `[dependencies]` entry, skipping those whose `[dependencies]` entry is
+/// one of `normal_findings`. Only needs `cargo metadata`, as the entries
+/// resolving to the same package have a `NodeDep` of both kinds.
+fn find_redundant_dev_dependencies(
+    structured_metadata: &StructuredMetadata,
+    workspace_members: &BTreeMap<cargo_metadata::PackageId, WorkspaceMember>,
+    target_platform: &platform::TargetPlatform,
+    normal_findings: &[&UnusedDependency],
+) -> anyhow::Result<HashSet<UnusedDependency>> {
+    let mut redundant_deps = HashSet::<UnusedDependency>::new();
+    for package_id in workspace_members.keys() {
+        let package: &cargo_metadata::Package = &structured_metadata.packages[package_id];
+        for node_dep in structured_metadata.nodes[package_id].deps.values() {
+            let dep_kinds: HashSet<DependencyKind> = dependency_kinds(node_dep)?;
+            if !dep_kinds.contains(&DependencyKind::Normal)
+                || !dep_kinds.contains(&DependencyKind::Development)
+            {
+                continue;
+            }
+            let dependency: &cargo_metadata::Package = &structured_metadata.packages[&node_dep.pkg];
+            // The entries which resolved to `node_dep`, as in
+            // `find_package_dependency`.
+            let mut normal_deps = Vec::<&cargo_metadata::Dependency>::new();
+            let mut dev_deps = Vec::<&cargo_metadata::Dependency>::new();
+            for dep in package.dependencies.iter() {
+                let is_node_dep: bool = dep.name == dependency.name
+                    && match dep.rename.as_ref() {
+                        Some(rename) => rename.replace('-', "_") == node_dep.name,
+                        None => dep.req.matches(&dependency.version),
+                    };
+                if !is_node_dep {
+                    continue;
+                }
+                match dep.kind {
+                    cargo_metadata::DependencyKind::Normal => normal_deps.push(dep),
+                    cargo_metadata::DependencyKind::Development => dev_deps.push(dep),
+                    _ => { /* not visible to the tests */ }
+                }
+            }
+            let manifest_key: &str = match dev_deps.first() {
+                Some(dev_dep) => manifest_key(dev_dep),
+                None => continue,
+            };
+            let is_normal_finding: bool = normal_findings.iter().any(|normal_finding| {
+                return normal_finding.dependant == *package_id
+                    && normal_finding.dep_kind == DependencyKind::Normal
+                    && normal_finding.manifest_key == manifest_key;
+            });
+            if is_normal_finding {
+                continue;
+            }
+
+            let mut targets = Vec::<Option<String>>::new();
+            for dev_dep in dev_deps.iter() {
+                let is_redundant: bool = target_platform.matches(dev_dep.target.as_ref())
+                    && normal_deps.iter().any(|normal_dep| {
+                        return is_subsumed_by(dev_dep, normal_dep);
+                    });
+                if is_redundant {
+                    targets.push(dev_dep.target.as_ref().map(|target| {
+                        return target.to_string();
+                    }));
+                }
+            }
+            if targets.is_empty() {
+                continue;
+            }
+            targets.sort();
+            targets.dedup();
+            redundant_deps.insert(UnusedDependency {
+                dependant: package_id.clone(),
+                dependency: node_dep.pkg.clone(),
+                dep_kind: DependencyKind::Development,
+
+                dependency_name: UnrenamedCrateOwned {
+                    name: Cow::Owned(dependency.name.clone()),
+                },
+                manifest_key: manifest_key.to_owned(),
+                dependant_manifest_path: package.manifest_path.clone(),
+                targets,
+                manifest_span: None,
+            });
+        }
+    }
+    return Ok(redundant_deps);
+}
+
+/// Whether the `[dependencies]` entry of `dep` is optional.
+fn is_optional_dependency(
+    structured_metadata: &StructuredMetadata,
+    dep: &UnusedDependency,
+) -> bool {
+    return structured_metadata.packages[&dep.dependant]
+        .dependencies
+        .iter()
+        .any(|package_dep| {
+            return package_dep.kind == cargo_metadata::DependencyKind::Normal
+                && manifest_key(package_dep) == dep.manifest_key
+                && package_dep.optional;
+        });
+}
+
+/// Sets the `targets` of each of `deps`.
+fn add_dependency_targets(
+    deps: HashSet<UnusedDependency>,
+    structured_metadata: &StructuredMetadata,
+    target_platform: &platform::TargetPlatform,
+) -> HashSet<UnusedDependency> {
+    let mut targeted_deps = HashSet::<UnusedDependency>::new();
+    for mut dep in deps.into_iter() {
+        dep.targets = dependency_targets(
+            structured_metadata.packages[&dep.dependant]
+                .dependencies
+                .as_slice(),
+            dep.manifest_key.as_str(),
+            dep.dep_kind,
+            target_platform,
+        );
+        targeted_deps.insert(dep);
+    }
+    return targeted_deps;
+}
+
+/// `target_platform` is that of `check_target`, queried once for every feature
+/// combination. Unused workspace dependencies are left to the caller, as they
+/// don't depend on the features.
 fn find_unused_dependencies_all_invocations(
     workspace: &Path,
     check_target: &CheckTarget,
+    target_platform: &platform::TargetPlatform,
     features: &Features,
     structured_metadata: &StructuredMetadata,
     check_doc_tests: bool,
@@ -1072,18 +2019,572 @@ fn find_unused_dependencies_all_invocations(
         }
     }
 
+    // Unused by the package itself, but used by its tests (or doc tests).
+    let mut test_only_deps = HashSet::<UnusedDependency>::new();
+    for dep in regular_lint_results.mismarked_dev_dependencies.into_iter() {
+        if combined_unused_deps.contains(&dep) {
+            continue;
+        }
+        // Dev-dependencies can't be optional, so it can't be moved.
+        if is_optional_dependency(structured_metadata, &dep) {
+            continue;
+        }
+        test_only_deps.insert(dep);
+    }
+
+    // Unused by the package, and its doc tests, but used by its build script.
+    let mut build_only_deps = HashSet::<UnusedDependency>::new();
+    for dep in regular_lint_results
+        .mismarked_build_dependencies
+        .into_iter()
+    {
+        // Moving an optional dependency would change the features enabling it,
+        // so it stays an unused dependency.
+        if !combined_unused_deps.contains(&dep) || is_optional_dependency(structured_metadata, &dep)
+        {
+            continue;
+        }
+        combined_unused_deps.remove(&dep);
+        build_only_deps.insert(dep);
+    }
+
+    // Only done now as `UnusedDependency` is compared above.
+    let mismarked_dev_dependencies: HashSet<UnusedDependency> = manifest::add_manifest_spans(
+        add_dependency_targets(test_only_deps, structured_metadata, target_platform),
+    )?;
+    let mismarked_build_dependencies: HashSet<UnusedDependency> = manifest::add_manifest_spans(
+        add_dependency_targets(build_only_deps, structured_metadata, target_platform),
+    )?;
+    let mut unused_dependencies: HashSet<UnusedDependency> = manifest::add_manifest_spans(
+        add_dependency_targets(combined_unused_deps, structured_metadata, target_platform),
+    )?;
+    let redundant_dev_dependencies: HashSet<UnusedDependency> =
+        manifest::add_manifest_spans(find_redundant_dev_dependencies(
+            structured_metadata,
+            &regular_lint_results.workspace_members,
+            target_platform,
+            // The `[dependencies]` entries which are removed, or moved by
+            // `--fix`.
+            &unused_dependencies
+                .iter()
+                .chain(mismarked_dev_dependencies.iter())
+                .chain(mismarked_build_dependencies.iter())
+                .collect::<Vec<&UnusedDependency>>(),
+        )?)?;
+    let suppressed_dependencies: HashSet<SuppressedDependency> =
+        manifest::remove_kept_dependencies(&mut unused_dependencies)?;
+
+    let mut checked_artifacts = regular_lint_results.checked_artifacts;
+    if check_doc_tests {
+        for ((package_id, dep_kind), descriptions) in checked_artifacts.iter_mut() {
+            let is_doc_tested: bool = match dep_kind {
+                DependencyKind::Normal | DependencyKind::Development => {
+                    has_lib_artifact(structured_metadata.packages[package_id].targets.as_slice())?
+                }
+                DependencyKind::Build => false,
+            };
+            if is_doc_tested {
+                descriptions.insert("doc tests".to_owned());
+            }
+        }
+    }
+
     return Ok(DependencyLintResults {
-        unused_dependencies: combined_unused_deps,
-        mismarked_dev_dependencies: (),
+        workspace_root: regular_lint_results.workspace_root,
+        workspace_members: regular_lint_results.workspace_members,
+        unused_dependencies,
+        suppressed_dependencies,
+        mismarked_dev_dependencies,
+        mismarked_build_dependencies,
+        redundant_dev_dependencies,
+        optional_candidates: HashSet::new(),
         orphans: regular_lint_results.orphans,
+        unused_workspace_dependencies: HashSet::new(),
+        workspace_levels: regular_lint_results.workspace_levels,
+        member_manifest_paths: regular_lint_results.member_manifest_paths,
+        checked_artifacts,
+        feature_combinations: Vec::new(),
+        fixed_baseline_entries: Vec::new(),
     });
 }
 
+/// Each combination of at most `depth` of `features`, fewest first.
+fn feature_powerset<'a>(features: &[&'a str], depth: Option<usize>) -> Vec<Vec<&'a str>> {
+    let max_len: usize = match depth {
+        Some(depth) => std::cmp::min(depth, features.len()),
+        None => features.len(),
+    };
+    let mut combinations: Vec<Vec<&str>> = vec![Vec::new()];
+    for feature in features.iter() {
+        let mut with_feature = Vec::<Vec<&str>>::new();
+        for combination in combinations.iter() {
+            if combination.len() < max_len {
+                let mut combination: Vec<&str> = combination.clone();
+                combination.push(feature);
+                with_feature.push(combination);
+            }
+        }
+        combinations.append(&mut with_feature);
+    }
+    combinations.sort_by(|a, b| return (a.len(), a).cmp(&(b.len(), b)));
+    return combinations;
+}
+
+/// The feature combinations checked by `--feature-powerset`: the default
+/// features, then each combination of at most `depth` features of each
+/// workspace member (`default` being one of them), with the other members at
+/// their default features.
+fn feature_powerset_combinations(
+    structured_metadata: &StructuredMetadata,
+    members: WorkspaceMembers,
+    depth: Option<usize>,
+) -> Vec<Features> {
+    let mut member_features = Vec::<(&str, Vec<&str>)>::new();
+    for package_id in workspace_members(structured_metadata, members).iter() {
+        let package: &cargo_metadata::Package = &structured_metadata.packages[package_id];
+        let features: Vec<&str> = package.features.keys().map(String::as_str).collect();
+        member_features.push((package.name.as_str(), features));
+    }
+    return member_powerset_combinations(&member_features, depth);
+}
+
+/// See `feature_powerset_combinations`, `member_features` are the name, and
+/// the features of each workspace member.
+fn member_powerset_combinations(
+    member_features: &[(&str, Vec<&str>)],
+    depth: Option<usize>,
+) -> Vec<Features> {
+    let mut member_features: Vec<&(&str, Vec<&str>)> = member_features.iter().collect();
+    member_features.sort();
+    // `--no-default-features` applies to every member, so the others enable
+    // their `default` feature instead.
+    let default_features = |skipped_package: Option<&str>| {
+        let mut features = Vec::<String>::new();
+        for (package_name, package_features) in member_features.iter() {
+            if Some(*package_name) != skipped_package && package_features.contains(&"default") {
+                features.push(format!("{}/default", package_name));
+            }
+        }
+        return features;
+    };
+    let all_defaults: Vec<String> = default_features(None);
+
+    let mut combinations: Vec<Features> = vec![Features::Default];
+    for (package_name, package_features) in member_features.iter() {
+        for combination in feature_powerset(package_features, depth).iter() {
+            let mut features: Vec<String> = default_features(Some(*package_name));
+            for feature in combination.iter() {
+                features.push(format!("{}/{}", package_name, feature));
+            }
+            features.sort();
+            // The default features are already checked.
+            if features == all_defaults {
+                continue;
+            }
+            combinations.push(Features::Specified {
+                features,
+                default_features: false,
+            });
+        }
+    }
+    return combinations;
+}
+
+/// What `cargo metadata` resolves with the features of a combination.
+struct ResolvedFeatures {
+    /// The (dependant, dependency, kind) of each dependency which is built,
+    /// optional dependencies are only built when a feature enables them.
+    enabled_deps: HashSet<(
+        cargo_metadata::PackageId,
+        cargo_metadata::PackageId,
+        DependencyKind,
+    )>,
+    /// The enabled features of each package, including those enabled by other
+    /// features.
+    enabled_features: HashMap<cargo_metadata::PackageId, BTreeSet<String>>,
+}
+
+fn resolve_features(workspace: &Path, features: &Features) -> anyhow::Result<ResolvedFeatures> {
+    let mut metadata_command = cargo_metadata::MetadataCommand::new();
+    metadata_command.current_dir(workspace);
+    match features {
+        Features::Specified {
+            features,
+            default_features,
+        } => {
+            if !default_features {
+                metadata_command.features(cargo_metadata::CargoOpt::NoDefaultFeatures);
+            }
+            if !features.is_empty() {
+                metadata_command.features(cargo_metadata::CargoOpt::SomeFeatures(features.clone()));
+            }
+        }
+        Features::Default => { /* do nothing */ }
+        Features::All => {
+            metadata_command.features(cargo_metadata::CargoOpt::AllFeatures);
+        }
+    }
+    let metadata: cargo_metadata::Metadata = metadata_command.exec()?;
+    let resolve: &cargo_metadata::Resolve = metadata
+        .resolve
+        .as_ref()
+        .context("Missing cargo_metadata resolve")?;
+
+    let mut resolved = ResolvedFeatures {
+        enabled_deps: HashSet::new(),
+        enabled_features: HashMap::with_capacity(resolve.nodes.len()),
+    };
+    for node in resolve.nodes.iter() {
+        for dep in node.deps.iter() {
+            for dep_kind in dependency_kinds(dep)?.into_iter() {
+                resolved
+                    .enabled_deps
+                    .insert((node.id.clone(), dep.pkg.clone(), dep_kind));
+            }
+        }
+        resolved.enabled_features.insert(
+            node.id.clone(),
+            node.features.iter().cloned().collect::<BTreeSet<String>>(),
+        );
+    }
+    return Ok(resolved);
+}
+
+/// The findings of any feature combination which, in every other combination,
+/// are also a finding, or are `accepted` (such as a dependency which isn't
+/// enabled by the combination).
+fn merge_combination_findings<T: Clone + Eq + std::hash::Hash>(
+    combination_findings: &[&HashSet<T>],
+    accepted: impl Fn(usize, &T) -> bool,
+) -> HashSet<T> {
+    let mut merged = HashSet::<T>::new();
+    for findings in combination_findings.iter() {
+        for finding in findings.iter() {
+            if combination_findings
+                .iter()
+                .enumerate()
+                .all(|(i, other)| return other.contains(finding) || accepted(i, finding))
+            {
+                merged.insert(finding.clone());
+            }
+        }
+    }
+    return merged;
+}
+
+/// The features of `package_features` enabled by `feature`, including itself.
+fn implied_features<'a>(
+    feature: &'a str,
+    package_features: &'a BTreeMap<String, Vec<String>>,
+) -> BTreeSet<&'a str> {
+    let mut implied = BTreeSet::<&str>::new();
+    let mut pending: Vec<&str> = vec![feature];
+    while let Some(feature) = pending.pop() {
+        if !implied.insert(feature) {
+            continue;
+        }
+        if let Some(values) = package_features.get(feature) {
+            for value in values.iter() {
+                // `dep:name`, and `name/feature` aren't features of the package.
+                if !value.contains(':') && !value.contains('/') {
+                    pending.push(value.as_str());
+                }
+            }
+        }
+    }
+    return implied;
+}
+
+/// The dependencies of `unused_deps` (those unused by some combination) which
+/// are used by every combination enabling a feature of their dependant, with
+/// each such feature which no other one enables. `default`, and the implicit
+/// features of optional dependencies aren't gating features. `is_used` is
+/// whether combination `i` uses the dependency, `enabled_features` has the
+/// enabled features of each package for each combination, and
+/// `feature_definitions` the `[features]` of each package.
+fn find_optional_candidates<'a>(
+    unused_deps: impl Iterator<Item = &'a UnusedDependency>,
+    enabled_features: &[&HashMap<cargo_metadata::PackageId, BTreeSet<String>>],
+    feature_definitions: &HashMap<&cargo_metadata::PackageId, &BTreeMap<String, Vec<String>>>,
+    is_used: impl Fn(usize, &UnusedDependency) -> bool,
+) -> HashSet<OptionalCandidate> {
+    let no_features = BTreeSet::<String>::new();
+    let no_definitions = BTreeMap::<String, Vec<String>>::new();
+    let mut optional_candidates = HashSet::<OptionalCandidate>::new();
+    for unused_dep in unused_deps {
+        // The features enabled by every combination using it.
+        let mut features: Option<BTreeSet<&str>> = None;
+        for (i, combination_features) in enabled_features.iter().enumerate() {
+            if !is_used(i, unused_dep) {
+                continue;
+            }
+            let combination_features: BTreeSet<&str> = combination_features
+                .get(&unused_dep.dependant)
+                .unwrap_or(&no_features)
+                .iter()
+                .map(String::as_str)
+                .collect();
+            features = Some(match features {
+                Some(features) => features
+                    .intersection(&combination_features)
+                    .cloned()
+                    .collect(),
+                None => combination_features,
+            });
+        }
+        let package_features: &BTreeMap<String, Vec<String>> = feature_definitions
+            .get(&unused_dep.dependant)
+            .copied()
+            .unwrap_or(&no_definitions);
+        let mut features: BTreeSet<&str> = features.unwrap_or_default();
+        features.retain(|feature| {
+            let is_implicit: bool = match package_features.get(*feature) {
+                Some(values) => *values == [format!("dep:{}", feature)],
+                None => false,
+            };
+            return *feature != "default" && !is_implicit;
+        });
+        // Without features, it is either unused, or used without any feature.
+        for feature in features.iter() {
+            let is_enabled_by_other: bool = features.iter().any(|other_feature| {
+                return other_feature != feature
+                    && implied_features(other_feature, package_features).contains(feature);
+            });
+            if !is_enabled_by_other {
+                optional_candidates.insert(OptionalCandidate {
+                    unused_dependency: unused_dep.clone(),
+                    feature: (*feature).to_owned(),
+                });
+            }
+        }
+    }
+    return optional_candidates;
+}
+
+struct CombinationResults {
+    features: Features,
+    resolved: ResolvedFeatures,
+    results: DependencyLintResults,
+}
+
+/// Combines the results of each `--feature-powerset` combination. A dependency
+/// is only unused (or mislabeled) if it is in every combination which enables
+/// it, artifacts are only orphans if they are in every combination. Other
+/// `[dependencies]` entries unused by some combinations may be optional
+/// candidates.
+fn merge_feature_combinations(
+    target_platform: &platform::TargetPlatform,
+    structured_metadata: &StructuredMetadata,
+    combinations: Vec<CombinationResults>,
+) -> anyhow::Result<DependencyLintResults> {
+    let is_disabled = |i: usize, dep: &UnusedDependency| -> bool {
+        return !combinations[i].resolved.enabled_deps.contains(&(
+            dep.dependant.clone(),
+            dep.dependency.clone(),
+            dep.dep_kind,
+        ));
+    };
+    let is_unused = |i: usize, dep: &UnusedDependency| -> bool {
+        return combinations[i].results.unused_dependencies.contains(dep);
+    };
+
+    let unused_dependencies: HashSet<UnusedDependency> = merge_combination_findings(
+        &combinations
+            .iter()
+            .map(|combination| return &combination.results.unused_dependencies)
+            .collect::<Vec<&HashSet<UnusedDependency>>>(),
+        |i, dep| return is_disabled(i, dep),
+    );
+    let suppressed_dependencies: HashSet<SuppressedDependency> = merge_combination_findings(
+        &combinations
+            .iter()
+            .map(|combination| return &combination.results.suppressed_dependencies)
+            .collect::<Vec<&HashSet<SuppressedDependency>>>(),
+        |i, dep| return is_disabled(i, &dep.unused_dependency),
+    );
+    // Mislabeled where the dependency is used, and unused elsewhere.
+    let mismarked_dev_dependencies: HashSet<UnusedDependency> = merge_combination_findings(
+        &combinations
+            .iter()
+            .map(|combination| return &combination.results.mismarked_dev_dependencies)
+            .collect::<Vec<&HashSet<UnusedDependency>>>(),
+        |i, dep| return is_unused(i, dep) || is_disabled(i, dep),
+    );
+    let mismarked_build_dependencies: HashSet<UnusedDependency> = merge_combination_findings(
+        &combinations
+            .iter()
+            .map(|combination| return &combination.results.mismarked_build_dependencies)
+            .collect::<Vec<&HashSet<UnusedDependency>>>(),
+        |i, dep| return is_unused(i, dep) || is_disabled(i, dep),
+    );
+    // `[dependencies]` entries unused by some combinations, but not all.
+    let mut partially_unused_deps = BTreeSet::<&UnusedDependency>::new();
+    for combination in combinations.iter() {
+        let combination_unused_deps = combination.results.unused_dependencies.iter().chain(
+            combination
+                .results
+                .suppressed_dependencies
+                .iter()
+                .map(|suppressed_dep| return &suppressed_dep.unused_dependency),
+        );
+        for unused_dep in combination_unused_deps {
+            if unused_dep.dep_kind == DependencyKind::Normal
+                && !is_optional_dependency(structured_metadata, unused_dep)
+                && !unused_dependencies.contains(unused_dep)
+                && !mismarked_dev_dependencies.contains(unused_dep)
+                && !mismarked_build_dependencies.contains(unused_dep)
+                && !suppressed_dependencies.iter().any(|suppressed_dep| {
+                    return suppressed_dep.unused_dependency == *unused_dep;
+                })
+            {
+                partially_unused_deps.insert(unused_dep);
+            }
+        }
+    }
+    let optional_candidates: HashSet<OptionalCandidate> = find_optional_candidates(
+        partially_unused_deps.into_iter(),
+        &combinations
+            .iter()
+            .map(|combination| return &combination.resolved.enabled_features)
+            .collect::<Vec<&HashMap<cargo_metadata::PackageId, BTreeSet<String>>>>(),
+        &structured_metadata
+            .packages
+            .iter()
+            .map(|(package_id, package)| return (package_id, &package.features))
+            .collect(),
+        |i, dep| {
+            return !is_unused(i, dep)
+                && !combinations[i]
+                    .results
+                    .suppressed_dependencies
+                    .iter()
+                    .any(|suppressed_dep| return suppressed_dep.unused_dependency == *dep);
+        },
+    );
+    let orphans: HashSet<OrphanArtifact> = merge_combination_findings(
+        &combinations
+            .iter()
+            .map(|combination| return &combination.results.orphans)
+            .collect::<Vec<&HashSet<OrphanArtifact>>>(),
+        |_, _| return false,
+    );
+
+    let mut checked_artifacts =
+        BTreeMap::<(cargo_metadata::PackageId, DependencyKind), BTreeSet<String>>::new();
+    let mut feature_combinations = Vec::<FeatureCombination>::new();
+    for combination in combinations.iter() {
+        for (key, descriptions) in combination.results.checked_artifacts.iter() {
+            checked_artifacts
+                .entry(key.clone())
+                .or_default()
+                .extend(descriptions.iter().cloned());
+        }
+        let flags: Vec<String> = compute_feature_args(&combination.features)
+            .iter()
+            .map(|arg| return arg.to_string_lossy().into_owned())
+            .collect();
+        feature_combinations.push(FeatureCombination {
+            flags: flags.join(" "),
+            features: combination.features.clone(),
+            unused_dependencies: combination.results.unused_dependencies.clone(),
+        });
+    }
+
+    let first: DependencyLintResults = combinations
+        .into_iter()
+        .next()
+        .context("No feature combinations were checked")?
+        .results;
+    // Recomputed, as it depends on which `[dependencies]` entries are findings.
+    let redundant_dev_dependencies: HashSet<UnusedDependency> =
+        manifest::add_manifest_spans(find_redundant_dev_dependencies(
+            structured_metadata,
+            &first.workspace_members,
+            target_platform,
+            &unused_dependencies
+                .iter()
+                .chain(mismarked_dev_dependencies.iter())
+                .chain(mismarked_build_dependencies.iter())
+                .collect::<Vec<&UnusedDependency>>(),
+        )?)?;
+
+    return Ok(DependencyLintResults {
+        workspace_root: first.workspace_root,
+        workspace_members: first.workspace_members,
+        unused_dependencies,
+        suppressed_dependencies,
+        mismarked_dev_dependencies,
+        mismarked_build_dependencies,
+        redundant_dev_dependencies,
+        optional_candidates,
+        orphans,
+        unused_workspace_dependencies: HashSet::new(),
+        workspace_levels: first.workspace_levels,
+        member_manifest_paths: first.member_manifest_paths,
+        checked_artifacts,
+        feature_combinations,
+        fixed_baseline_entries: Vec::new(),
+    });
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// Debug formatted output intended for humans.
+    Human,
+    /// A versioned JSON document, see the README for the schema.
+    Json,
+    /// A SARIF 2.1.0 log for code scanning tools.
+    Sarif,
+    /// Line delimited `cargo --message-format=json` compatible messages with
+    /// diagnostics pointing into Cargo.toml files.
+    CargoJson,
+    /// GitHub Actions annotations, and a job summary appended to
+    /// `$GITHUB_STEP_SUMMARY`.
+    Github,
+    /// A JUnit XML report with a test suite per workspace member, and a failing
+    /// test case per finding.
+    Junit,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum FixMode {
+    /// Remove every unused dependency.
+    Auto,
+    /// Ask whether to keep, remove, or ignore each unused dependency, reading
+    /// the answers from stdin.
+    Interactive,
+}
+
+// Splits `--fix` into reviewable steps.
+#[derive(clap::Subcommand)]
+pub enum FixCommand {
+    /// Write the manifest edits `--fix` would make to a JSON plan file, instead
+    /// of making them.
+    Plan {
+        /// The plan file to write.
+        path: PathBuf,
+    },
+    /// Make the edits of a plan written by `reves plan`, without linting the
+    /// workspace again. Manifests which have changed since the plan was made
+    /// aren't edited.
+    Apply {
+        /// The plan file to read.
+        path: PathBuf,
+    },
+}
+
 #[derive(clap::Parser)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<FixCommand>,
+
     #[arg(long, default_value_t)]
     color: clap::ColorChoice,
 
+    /// The format used to report results on stdout.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
     /// Passed to `cargo` invocations.
     #[arg(long, default_value_t = false)]
     frozen: bool,
@@ -1098,8 +2599,32 @@ pub struct Args {
 
     /// Attempt to automatically correct Cargo.toml files. This feature is
     /// currently experimental, and may cause unexpected behavior.
-    #[arg(long, default_value_t = false)]
-    fix: bool,
+    /// `--fix=interactive` asks whether to remove each unused dependency.
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto"
+    )]
+    fix: Option<FixMode>,
+
+    /// With `--fix`, check that the workspace (and its doc tests with
+    /// `--check-doc-tests`) still builds after the removals, and roll back the
+    /// ones which break it.
+    #[arg(long, action = clap::ArgAction::Set, default_value_t = true)]
+    verify_fix: bool,
+
+    /// With `--fix`, print a unified diff of the Cargo.toml files which would be
+    /// changed on stdout, instead of writing them. The report is printed on
+    /// stderr.
+    #[arg(
+        long,
+        visible_alias = "diff",
+        requires = "fix",
+        default_value_t = false
+    )]
+    dry_run: bool,
 
     /// Requires nightly, but without this flag the tool make declare
     /// dev-dependencies as unused when they are used.
@@ -1111,6 +2636,63 @@ pub struct Args {
     #[arg(long, action = clap::ArgAction::Set, default_value_t = false)]
     allow_orphaned_artifacts: bool,
 
+    /// Record the current findings in a baseline file, they are then not
+    /// reported by this, or later runs using `--baseline`.
+    #[arg(long, conflicts_with = "baseline")]
+    write_baseline: Option<PathBuf>,
+
+    /// Only report findings which aren't in this baseline file (written by
+    /// `--write-baseline`).
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Also print unused dependencies suppressed by `# reves: keep` comments,
+    /// or `ignored` entries, and those of each `--feature-powerset`
+    /// combination in the human output.
+    #[arg(long, short, default_value_t = false)]
+    verbose: bool,
+
+    /// Comma separated categories of findings which make `reves` exit with
+    /// status 1, overriding the levels in `[workspace.metadata.reves]`, and
+    /// `[package.metadata.reves]`. The other categories are at most warned
+    /// about.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    deny: Vec<LintCategory>,
+
+    /// Comma separated features to check with, such as `std`, or
+    /// `magenta/std` for a workspace member. By default, every feature is
+    /// enabled.
+    #[arg(long, value_delimiter = ',')]
+    features: Vec<String>,
+
+    /// Check without the default features.
+    #[arg(long, default_value_t = false)]
+    no_default_features: bool,
+
+    /// Check with every feature of the workspace members, the default.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["features", "no_default_features"]
+    )]
+    all_features: bool,
+
+    /// Check each combination of the features of each workspace member
+    /// (without the default features), and only report dependencies which are
+    /// unused in every combination which enables them. `--verbose` prints the
+    /// unused dependencies of each combination.
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["features", "no_default_features", "all_features"]
+    )]
+    feature_powerset: bool,
+
+    /// With `--feature-powerset`, the most features of a workspace member
+    /// enabled in a combination.
+    #[arg(long, requires = "feature_powerset")]
+    depth: Option<usize>,
+
     /// Passed to `cargo` invocations.
     #[arg(long)]
     workspace: bool,
@@ -1131,94 +2713,387 @@ pub struct Args {
 pub fn lint_dependencies(
     workspace: &Path,
     check_doc_tests: bool,
+    feature_selection: &FeatureSelection,
     cargo_args: &CargoArgs,
 ) -> anyhow::Result<DependencyLintResults> {
     let cargo_version: semver::Version = cargo_version(workspace)?;
-    /* TODO: properly match arguments of the cargo check command... */
+    /*
+      TODO: properly match arguments of the cargo check command...
+
+      With all features, so every dependency of every combination is found.
+    */
     let metadata: cargo_metadata::Metadata = cargo_metadata::MetadataCommand::new()
         .current_dir(workspace)
         .features(cargo_metadata::CargoOpt::AllFeatures)
         .exec()?;
     let structured_metadata: StructuredMetadata =
         metadata_to_structured_metadata(&metadata, &cargo_version)?;
-    return find_unused_dependencies_all_invocations(
-        workspace,
-        &CheckTarget::Host,
-        &Features::All,
-        &structured_metadata,
-        check_doc_tests,
-        cargo_args,
-    );
+    // The same for every feature combination.
+    let check_target = CheckTarget::Host;
+    let target_platform = platform::TargetPlatform::query(workspace, &check_target)?;
+    let unused_workspace_dependencies: HashSet<UnusedWorkspaceDependency> =
+        manifest::find_unused_workspace_dependencies(
+            &structured_metadata.workspace_root.join("Cargo.toml"),
+            &member_manifest_paths(&structured_metadata),
+        )?;
+
+    let mut lint_results: DependencyLintResults = match feature_selection {
+        FeatureSelection::Powerset { depth } => {
+            let mut combinations = Vec::<CombinationResults>::new();
+            for features in feature_powerset_combinations(
+                &structured_metadata,
+                WorkspaceMembers::from_workspace_arg(cargo_args.workspace),
+                *depth,
+            )
+            .into_iter()
+            {
+                let results: DependencyLintResults = find_unused_dependencies_all_invocations(
+                    workspace,
+                    &check_target,
+                    &target_platform,
+                    &features,
+                    &structured_metadata,
+                    check_doc_tests,
+                    cargo_args,
+                )?;
+                combinations.push(CombinationResults {
+                    resolved: resolve_features(workspace, &features)?,
+                    features,
+                    results,
+                });
+            }
+            merge_feature_combinations(&target_platform, &structured_metadata, combinations)?
+        }
+        FeatureSelection::All | FeatureSelection::Specified { .. } => {
+            find_unused_dependencies_all_invocations(
+                workspace,
+                &check_target,
+                &target_platform,
+                &feature_selection.checked_features(),
+                &structured_metadata,
+                check_doc_tests,
+                cargo_args,
+            )?
+        }
+    };
+    lint_results.unused_workspace_dependencies = unused_workspace_dependencies;
+    return Ok(lint_results);
+}
+
+/// Returns `EXIT_STALE_PLAN` if any manifest changed since the plan was made.
+fn apply(args: &Args, plan_path: &Path) -> anyhow::Result<u8> {
+    let mut metadata_command = cargo_metadata::MetadataCommand::new();
+    metadata_command.current_dir(Path::new(".")).no_deps();
+    if let Some(manifest_path) = args.manifest_path.as_ref() {
+        metadata_command.manifest_path(manifest_path);
+    }
+    let metadata: cargo_metadata::Metadata = metadata_command.exec()?;
+
+    let edits: Vec<plan::PlanEdit> = plan::read_plan(plan_path)?;
+    let (fixes, refused) = plan::apply_plan(&metadata.workspace_root, &edits)?;
+    fix::write_fixes(&fixes)?;
+    for manifest_path in fixes.keys() {
+        eprintln!("Applied the plan to {}", manifest_path);
+    }
+    for (manifest_path, reason) in refused.iter() {
+        eprintln!(
+            "Warning: {} has changed since the plan was made ({}), it wasn't edited",
+            manifest_path, reason
+        );
+    }
+    if !refused.is_empty() {
+        return Ok(EXIT_STALE_PLAN);
+    }
+    return Ok(EXIT_CLEAN);
+}
+
+/// Writes the report in the `--message-format` of `args`.
+fn write_report(
+    args: &Args,
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    match args.message_format {
+        MessageFormat::Human => {
+            writeln!(writer, "{:#?}", results.unused_dependencies)?;
+            writeln!(
+                writer,
+                "Found #{} unused dependencies",
+                results.unused_dependencies.len()
+            )?;
+
+            writeln!(writer, "{:#?}", results.mismarked_dev_dependencies)?;
+            writeln!(
+                writer,
+                "Found #{} mislabeled dev-dependencies",
+                results.mismarked_dev_dependencies.len()
+            )?;
+
+            writeln!(writer, "{:#?}", results.mismarked_build_dependencies)?;
+            writeln!(
+                writer,
+                "Found #{} mislabeled build-dependencies",
+                results.mismarked_build_dependencies.len()
+            )?;
+
+            writeln!(writer, "{:#?}", results.redundant_dev_dependencies)?;
+            writeln!(
+                writer,
+                "Found #{} redundant dev-dependencies",
+                results.redundant_dev_dependencies.len()
+            )?;
+
+            writeln!(writer, "{:#?}", results.optional_candidates)?;
+            writeln!(
+                writer,
+                "Found #{} dependencies which could be optional",
+                results.optional_candidates.len()
+            )?;
+
+            if !args.allow_orphaned_artifacts {
+                writeln!(writer, "{:#?}", results.orphans)?;
+                writeln!(writer, "Found #{} orphan artifacts", results.orphans.len())?;
+            }
+
+            writeln!(writer, "{:#?}", results.unused_workspace_dependencies)?;
+            writeln!(
+                writer,
+                "Found #{} unused workspace dependencies",
+                results.unused_workspace_dependencies.len()
+            )?;
+
+            if args.verbose {
+                writeln!(writer, "{:#?}", results.suppressed_dependencies)?;
+                writeln!(
+                    writer,
+                    "Suppressed #{} unused dependencies",
+                    results.suppressed_dependencies.len()
+                )?;
+
+                for combination in results.feature_combinations.iter() {
+                    writeln!(writer, "{:#?}", combination.unused_dependencies)?;
+                    writeln!(
+                        writer,
+                        "Found #{} unused dependencies with {}",
+                        combination.unused_dependencies.len(),
+                        if combination.flags.is_empty() {
+                            "the default features"
+                        } else {
+                            combination.flags.as_str()
+                        }
+                    )?;
+                }
+            }
+        }
+        MessageFormat::Json => {
+            json::write_json_report(results, writer)?;
+        }
+        MessageFormat::Sarif => {
+            sarif::write_sarif_report(results, writer)?;
+        }
+        MessageFormat::CargoJson => {
+            diagnostics::write_cargo_json_messages(results, writer)?;
+        }
+        MessageFormat::Github => {
+            github::write_github_report(results, writer)?;
+        }
+        MessageFormat::Junit => {
+            junit::write_junit_report(results, writer)?;
+        }
+    }
+    return Ok(());
 }
 
-pub fn lib_main(args: &Args) {
-    let cargo_version: semver::Version = cargo_version(Path::new(".")).unwrap();
+/// Returns the exit status.
+fn run(args: &Args) -> anyhow::Result<u8> {
+    if let Some(FixCommand::Apply { path }) = args.command.as_ref() {
+        return apply(args, path);
+    }
+    let cargo_version: semver::Version = cargo_version(Path::new("."))?;
     if !args.workspace {
-        assert!(
+        anyhow::ensure!(
             supports_default_workspace_members(&cargo_version),
             "You must pass --workspace if cargo is <1.71 due to cargo/cargo_metadata deficiencies"
         );
     }
+    anyhow::ensure!(
+        args.fix.is_none() || args.command.is_none(),
+        "--fix can't be combined with `reves plan`"
+    );
+    anyhow::ensure!(
+        !args.dry_run || args.fix != Some(FixMode::Interactive),
+        "--dry-run can't be combined with --fix=interactive"
+    );
 
-    let lint_results: DependencyLintResults = lint_dependencies(
+    let cargo_args = CargoArgs {
+        color: args.color,
+        frozen: args.frozen,
+        locked: args.locked,
+        offline: args.offline,
+        workspace: args.workspace,
+        config: args.config.clone(),
+        target_dir: args.target_dir.clone(),
+        manifest_path: args.manifest_path.clone(),
+    };
+    let feature_selection: FeatureSelection = if args.feature_powerset {
+        FeatureSelection::Powerset { depth: args.depth }
+    } else if !args.features.is_empty() || args.no_default_features {
+        FeatureSelection::Specified {
+            features: args.features.clone(),
+            default_features: !args.no_default_features,
+        }
+    } else {
+        FeatureSelection::All
+    };
+    let mut lint_results: DependencyLintResults = lint_dependencies(
         Path::new("."),
         args.check_doc_tests,
-        &CargoArgs {
-            color: args.color,
-            frozen: args.frozen,
-            locked: args.locked,
-            offline: args.offline,
-            workspace: args.workspace,
-            config: args.config.clone(),
-            target_dir: args.target_dir.clone(),
-            manifest_path: args.manifest_path.clone(),
-        },
-    )
-    .unwrap();
+        &feature_selection,
+        &cargo_args,
+    )?;
+    if !args.deny.is_empty() {
+        lint_results.workspace_levels.override_deny(&args.deny);
+    }
+    for member in lint_results.workspace_members.values_mut() {
+        if !args.deny.is_empty() {
+            member.levels.override_deny(&args.deny);
+        }
+        if args.allow_orphaned_artifacts {
+            member.levels.orphans = Level::Allow;
+        }
+    }
+    for (package_id, ignored) in lint_results.remove_ignored_findings().iter() {
+        eprintln!(
+            "Warning: {} ignores `{}` which isn't an unused dependency, the entry can be removed",
+            lint_results.workspace_members[package_id].manifest_path, ignored.name,
+        );
+    }
+    lint_results.remove_allowed_findings();
 
-    println!("{:#?}", lint_results.unused_dependencies);
-    println!(
-        "Found #{} unused dependencies",
-        lint_results.unused_dependencies.len()
-    );
+    let baseline: Option<BTreeSet<BaselineEntry>> = if let Some(path) = args.write_baseline.as_ref()
+    {
+        let entries: BTreeSet<BaselineEntry> = baseline::baseline_entries(&lint_results);
+        baseline::write_baseline(path, &entries)?;
+        Some(entries)
+    } else if let Some(path) = args.baseline.as_ref() {
+        Some(baseline::read_baseline(path)?)
+    } else {
+        None
+    };
+    if let Some(baseline) = baseline.as_ref() {
+        lint_results.fixed_baseline_entries =
+            baseline::remove_baselined_findings(&mut lint_results, baseline);
+        for entry in lint_results.fixed_baseline_entries.iter() {
+            eprintln!(
+                "Warning: the baseline entry for the {} has been fixed, update the baseline with --write-baseline",
+                entry
+            );
+        }
+    }
 
-    if !args.allow_orphaned_artifacts {
-        println!("{:#?}", lint_results.orphans);
-        println!("Found #{} orphan artifacts", lint_results.orphans.len());
-    }
-
-    if args.fix {
-        for unused_dep in lint_results.unused_dependencies.iter() {
-            let manifest_path: &Utf8Path = unused_dep.dependant_manifest_path.as_path();
-            let manifest_data: String = std::fs::read_to_string(manifest_path).unwrap();
-            /* todo support [target."foo".dependencies] syntax? */
-            let mut document = toml_edit::Document::from_str(manifest_data.as_str()).unwrap();
-
-            let mut handled: bool = false;
-            for (name, item) in document.iter_mut() {
-                if let Some(dep_kind) = toml_key_to_dep_kind(name.get()) {
-                    if dep_kind == unused_dep.dep_kind {
-                        if let Some(table) = item.as_table_mut() {
-                            if table
-                                .remove(unused_dep.dependency_name.name.borrow())
-                                .is_some()
-                            {
-                                if handled {
-                                    eprintln!("Warning: handled multiple times {:#?}", unused_dep,);
-                                }
-                                handled = true;
-                            }
-                        }
-                    }
-                }
-            }
-            if !handled {
-                eprintln!("Warning: unable to fix {:#?}", unused_dep);
-            } else {
-                std::fs::write(manifest_path, document.to_string()).unwrap();
-            }
+    // With `--dry-run` stdout only has the diff, so it can be applied.
+    if args.dry_run {
+        write_report(args, &lint_results, &mut std::io::stderr().lock())?;
+    } else {
+        write_report(args, &lint_results, &mut std::io::stdout().lock())?;
+    }
+
+    // Computed before `--fix=interactive` drops the kept findings.
+    let denied_findings: usize = lint_results.denied_findings();
+    if args.fix == Some(FixMode::Interactive) {
+        let decisions: Vec<(&UnusedDependency, interactive::Decision)> =
+            interactive::ask_decisions(
+                &lint_results,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stderr().lock(),
+            )?;
+        fix::write_fixes(&interactive::ignore_dependencies(&decisions)?)?;
+        let removed_deps: HashSet<UnusedDependency> = interactive::removed_dependencies(&decisions);
+        interactive::retain_removals(&mut lint_results, removed_deps);
+    }
+
+    let unused_deps: Vec<&UnusedDependency> = lint_results.unused_dependencies.iter().collect();
+    if let Some(FixCommand::Plan { path }) = args.command.as_ref() {
+        let fixes: BTreeMap<Utf8PathBuf, fix::ManifestFix> =
+            fix::unused_dependency_fixes(&lint_results, &unused_deps)?;
+        plan::write_plan(
+            path,
+            &plan::plan_edits(&lint_results.workspace_root, &fixes)?,
+        )?;
+    } else if args.fix.is_some() && args.verify_fix && !args.dry_run {
+        let doc_test_packages: Vec<String> = if args.check_doc_tests {
+            doc_test_packages(Path::new("."), &cargo_args)?
+        } else {
+            Vec::new()
+        };
+        // The feature combinations which were checked for unused dependencies.
+        let checked_features: Vec<Features> = if lint_results.feature_combinations.is_empty() {
+            vec![feature_selection.checked_features()]
+        } else {
+            lint_results
+                .feature_combinations
+                .iter()
+                .map(|combination| {
+                    return combination.features.clone();
+                })
+                .collect()
+        };
+        let verified_fixes: fix::VerifiedFixes =
+            fix::write_verified_fixes(&lint_results, &mut || {
+                return workspace_builds(
+                    Path::new("."),
+                    &CheckTarget::Host,
+                    &checked_features,
+                    &doc_test_packages,
+                    &cargo_args,
+                );
+            })?;
+        for edit in verified_fixes.kept.iter() {
+            eprintln!("Kept {}", edit);
+        }
+        for edit in verified_fixes.rolled_back.iter() {
+            eprintln!(
+                "Warning: rolled back {}, as the workspace no longer builds with it",
+                edit
+            );
+        }
+    } else if args.fix.is_some() {
+        let fixes: BTreeMap<Utf8PathBuf, fix::ManifestFix> =
+            fix::unused_dependency_fixes(&lint_results, &unused_deps)?;
+        if args.dry_run {
+            fix::write_diff(
+                &lint_results.workspace_root,
+                &fixes,
+                &mut std::io::stdout().lock(),
+            )?;
+        } else {
+            fix::write_fixes(&fixes)?;
         }
     }
+
+    if denied_findings > 0 {
+        return Ok(EXIT_FINDINGS);
+    }
+    return Ok(EXIT_CLEAN);
+}
+
+/// No findings in the denied categories.
+pub const EXIT_CLEAN: u8 = 0;
+/// At least one finding in the denied categories.
+pub const EXIT_FINDINGS: u8 = 1;
+/// `reves` or `cargo` failed, the results are unknown.
+pub const EXIT_ERROR: u8 = 2;
+/// `reves apply` didn't edit a manifest which changed since the plan was made.
+pub const EXIT_STALE_PLAN: u8 = 3;
+
+pub fn lib_main(args: &Args) -> std::process::ExitCode {
+    return match run(args) {
+        Ok(exit_status) => std::process::ExitCode::from(exit_status),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            std::process::ExitCode::from(EXIT_ERROR)
+        }
+    };
 }
 
 #[cfg(test)]
@@ -1323,4 +3198,552 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_compute_feature_args() {
+        struct FeatureArgsTest {
+            features: super::Features,
+            expected: &'static [&'static str],
+        }
+        let feature_args_tests: &[FeatureArgsTest] = &[
+            FeatureArgsTest {
+                features: super::Features::All,
+                expected: &["--all-features"],
+            },
+            FeatureArgsTest {
+                features: super::Features::Default,
+                expected: &[],
+            },
+            FeatureArgsTest {
+                features: super::Features::Specified {
+                    features: vec!["std".to_owned(), "magenta/serde".to_owned()],
+                    default_features: true,
+                },
+                expected: &["--features", "std,magenta/serde"],
+            },
+            FeatureArgsTest {
+                features: super::Features::Specified {
+                    features: Vec::new(),
+                    default_features: false,
+                },
+                expected: &["--no-default-features"],
+            },
+        ];
+        for feature_args_test in feature_args_tests.iter() {
+            let args: Vec<String> = super::compute_feature_args(&feature_args_test.features)
+                .iter()
+                .map(|arg| return arg.to_str().unwrap().to_owned())
+                .collect();
+            assert_eq!(
+                args, feature_args_test.expected,
+                "{:?}",
+                feature_args_test.features
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_args() {
+        let cargo_args = super::CargoArgs {
+            color: clap::ColorChoice::Never,
+            frozen: false,
+            locked: true,
+            offline: false,
+            workspace: true,
+            config: Vec::new(),
+            target_dir: None,
+            manifest_path: None,
+        };
+        let features = super::Features::Specified {
+            features: vec!["magenta/std".to_owned()],
+            default_features: false,
+        };
+        let target = super::CheckTarget::Target("x86_64-unknown-linux-gnu".to_owned());
+        let to_strings = |args: Vec<std::borrow::Cow<'_, std::ffi::OsStr>>| {
+            return args
+                .iter()
+                .map(|arg| return arg.to_str().unwrap().to_owned())
+                .collect::<Vec<String>>();
+        };
+
+        let check_args = super::check_command(&target, &features, &cargo_args).args;
+        assert_eq!(
+            to_strings(check_args),
+            [
+                "check",
+                "--color",
+                "never",
+                "--locked",
+                "--workspace",
+                "--all-targets",
+                "--target-dir=target_reves",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--no-default-features",
+                "--features",
+                "magenta/std",
+            ]
+        );
+        // Run for each package, so without `--workspace`.
+        let doc_test_args = super::doc_test_command(&target, &features, &cargo_args).args;
+        assert_eq!(
+            to_strings(doc_test_args),
+            [
+                "test",
+                "--color",
+                "never",
+                "--locked",
+                "--quiet",
+                "--doc",
+                "--target-dir=target_reves_doc",
+                "--target",
+                "x86_64-unknown-linux-gnu",
+                "--no-default-features",
+                "--features",
+                "magenta/std",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_feature_powerset() {
+        assert_eq!(super::feature_powerset(&[], None), [Vec::<&str>::new()]);
+        assert_eq!(
+            super::feature_powerset(&["alloc", "serde", "std"], None),
+            [
+                vec![],
+                vec!["alloc"],
+                vec!["serde"],
+                vec!["std"],
+                vec!["alloc", "serde"],
+                vec!["alloc", "std"],
+                vec!["serde", "std"],
+                vec!["alloc", "serde", "std"],
+            ]
+        );
+        assert_eq!(
+            super::feature_powerset(&["alloc", "serde", "std"], Some(1)),
+            [vec![], vec!["alloc"], vec!["serde"], vec!["std"]]
+        );
+    }
+
+    #[test]
+    fn test_member_powerset_combinations() {
+        use crate::Features;
+
+        let specified = |features: &[&str]| {
+            return Features::Specified {
+                features: features
+                    .iter()
+                    .map(|feature| return (*feature).to_owned())
+                    .collect(),
+                default_features: false,
+            };
+        };
+        let member_features = [
+            ("purple", vec!["alloc", "serde"]),
+            ("magenta", vec!["default", "std"]),
+        ];
+        assert_eq!(
+            super::member_powerset_combinations(&member_features, None),
+            [
+                Features::Default,
+                specified(&[]),
+                specified(&["magenta/std"]),
+                specified(&["magenta/default", "magenta/std"]),
+                specified(&["magenta/default", "purple/alloc"]),
+                specified(&["magenta/default", "purple/serde"]),
+                specified(&["magenta/default", "purple/alloc", "purple/serde"]),
+            ]
+        );
+        assert_eq!(
+            super::member_powerset_combinations(&member_features, Some(1)),
+            [
+                Features::Default,
+                specified(&[]),
+                specified(&["magenta/std"]),
+                specified(&["magenta/default", "purple/alloc"]),
+                specified(&["magenta/default", "purple/serde"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_combination_findings() {
+        use std::collections::HashSet;
+
+        let combination_findings: [HashSet<&str>; 3] = [
+            HashSet::from(["fuchsia", "purple", "violet"]),
+            HashSet::from(["fuchsia", "purple"]),
+            HashSet::from(["fuchsia"]),
+        ];
+        let combination_findings: Vec<&HashSet<&str>> = combination_findings.iter().collect();
+        // `purple` isn't enabled by the last combination, `violet` is used by the
+        // second.
+        assert_eq!(
+            super::merge_combination_findings(&combination_findings, |i, finding| {
+                return i == 2 && *finding == "purple";
+            }),
+            HashSet::from(["fuchsia", "purple"])
+        );
+    }
+
+    #[test]
+    fn test_find_optional_candidates() {
+        use std::collections::BTreeMap;
+        use std::collections::BTreeSet;
+        use std::collections::HashMap;
+        use std::collections::HashSet;
+
+        use crate::test_util;
+        use crate::DependencyKind;
+
+        let fuchsia = test_util::unused_dependency("magenta", "fuchsia", DependencyKind::Normal);
+        let purple = test_util::unused_dependency("magenta", "purple", DependencyKind::Normal);
+        let violet = test_util::unused_dependency("magenta", "violet", DependencyKind::Normal);
+        let barley = test_util::unused_dependency("magenta", "barley", DependencyKind::Normal);
+        let lapin = test_util::unused_dependency("magenta", "lapin", DependencyKind::Normal);
+        let features = |features: &[&str]| {
+            return HashMap::from([(
+                test_util::package_id("magenta"),
+                features
+                    .iter()
+                    .map(|feature| return feature.to_string())
+                    .collect::<BTreeSet<String>>(),
+            )]);
+        };
+        let magenta_id = test_util::package_id("magenta");
+        let magenta_features = BTreeMap::from([
+            ("default".to_owned(), vec!["std".to_owned()]),
+            (
+                "fast".to_owned(),
+                vec!["std".to_owned(), "rye/simd".to_owned()],
+            ),
+            ("rye".to_owned(), vec!["dep:rye".to_owned()]),
+            ("serde".to_owned(), Vec::new()),
+            ("std".to_owned(), Vec::new()),
+        ]);
+        let feature_definitions = HashMap::from([(&magenta_id, &magenta_features)]);
+        // `default` enables `std`, and `fast` enables `std`, and the implicit
+        // `rye` feature.
+        let enabled_features = [
+            features(&[]),
+            features(&["std"]),
+            features(&["default", "std"]),
+            features(&["serde"]),
+            features(&["fast", "rye", "std"]),
+            features(&["default", "fast", "rye", "std"]),
+        ];
+        // `fuchsia` is used with `std`, `purple` with `std`, or `serde`,
+        // `violet` by no combination, `barley` with `default`, and `lapin` with
+        // `fast`.
+        let used = HashSet::from([
+            (1, "fuchsia"),
+            (2, "fuchsia"),
+            (1, "purple"),
+            (3, "purple"),
+            (2, "barley"),
+            (4, "lapin"),
+            (5, "lapin"),
+        ]);
+        assert_eq!(
+            super::find_optional_candidates(
+                [&fuchsia, &purple, &violet, &barley, &lapin].into_iter(),
+                &enabled_features.iter().collect::<Vec<_>>(),
+                &feature_definitions,
+                |i, dep| return used.contains(&(i, dep.manifest_key.as_str())),
+            ),
+            HashSet::from([
+                super::OptionalCandidate {
+                    unused_dependency: fuchsia.clone(),
+                    feature: "std".to_owned(),
+                },
+                super::OptionalCandidate {
+                    unused_dependency: barley.clone(),
+                    feature: "std".to_owned(),
+                },
+                super::OptionalCandidate {
+                    unused_dependency: lapin.clone(),
+                    feature: "fast".to_owned(),
+                },
+            ])
+        );
+    }
+
+    fn dependency(name: &str, req: &str, rename: Option<&str>) -> cargo_metadata::Dependency {
+        return serde_json::from_value(serde_json::json!({
+            "name": name,
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "req": req,
+            "kind": null,
+            "optional": false,
+            "uses_default_features": true,
+            "features": [],
+            "target": null,
+            "rename": rename,
+            "registry": null,
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn test_is_subsumed_by() {
+        let normal: cargo_metadata::Dependency = dependency("rand", "^0.8", None);
+        let mut unix_normal: cargo_metadata::Dependency = normal.clone();
+        unix_normal.target = Some(serde_json::from_value(serde_json::json!("cfg(unix)")).unwrap());
+        let mut std_normal: cargo_metadata::Dependency = normal.clone();
+        std_normal.features = vec!["std".to_owned()];
+        let mut optional_normal: cargo_metadata::Dependency = normal.clone();
+        optional_normal.optional = true;
+        let mut no_default_normal: cargo_metadata::Dependency = normal.clone();
+        no_default_normal.uses_default_features = false;
+
+        let mut unix_dev: cargo_metadata::Dependency = normal.clone();
+        unix_dev.target = unix_normal.target.clone();
+        let mut std_dev: cargo_metadata::Dependency = normal.clone();
+        std_dev.features = vec!["std".to_owned()];
+        let mut no_default_dev: cargo_metadata::Dependency = normal.clone();
+        no_default_dev.uses_default_features = false;
+
+        let subsumed_tests: &[(
+            &cargo_metadata::Dependency,
+            &cargo_metadata::Dependency,
+            bool,
+        )] = &[
+            (&normal, &normal, true),
+            (&unix_dev, &normal, true),
+            (&unix_dev, &unix_normal, true),
+            (&normal, &unix_normal, false),
+            (&std_dev, &std_normal, true),
+            (&normal, &std_normal, true),
+            // Adds features for the tests.
+            (&std_dev, &normal, false),
+            (&normal, &no_default_normal, false),
+            (&no_default_dev, &normal, true),
+            (&normal, &optional_normal, false),
+            (&dependency("rand", "^0.8.5", None), &normal, false),
+        ];
+        for (dev, normal, is_subsumed) in subsumed_tests.iter() {
+            assert_eq!(
+                super::is_subsumed_by(dev, normal),
+                *is_subsumed,
+                "{:?} {:?}",
+                dev,
+                normal
+            );
+        }
+    }
+
+    struct PackageDependencyTest {
+        renamed_crate: &'static str,
+        version: &'static str,
+        manifest_key: &'static str,
+    }
+
+    #[test]
+    fn test_find_package_dependency() {
+        let deps: Vec<cargo_metadata::Dependency> = vec![
+            dependency("rand", "^0.7", Some("rand07")),
+            dependency("rand", "^0.8", None),
+            dependency("rand", "^0.6", None),
+            dependency("rand", "^0.5", Some("rand-05")),
+        ];
+        let dependency_tests: &[PackageDependencyTest] = &[
+            PackageDependencyTest {
+                renamed_crate: "rand07",
+                version: "0.7.3",
+                manifest_key: "rand07",
+            },
+            PackageDependencyTest {
+                renamed_crate: "rand",
+                version: "0.8.5",
+                manifest_key: "rand",
+            },
+            PackageDependencyTest {
+                renamed_crate: "rand",
+                version: "0.6.5",
+                manifest_key: "rand",
+            },
+            PackageDependencyTest {
+                renamed_crate: "rand_05",
+                version: "0.5.6",
+                manifest_key: "rand-05",
+            },
+        ];
+        for dependency_test in dependency_tests.iter() {
+            let version = semver::Version::parse(dependency_test.version).unwrap();
+            let dep: &cargo_metadata::Dependency = super::find_package_dependency(
+                super::UnrenamedCrate {
+                    name: Cow::Borrowed("rand"),
+                },
+                super::RenamedCrate {
+                    name: Cow::Borrowed(dependency_test.renamed_crate),
+                },
+                &version,
+                deps.as_slice(),
+            )
+            .unwrap();
+            assert_eq!(super::manifest_key(dep), dependency_test.manifest_key);
+            assert!(dep.req.matches(&version), "{}", dependency_test.version);
+        }
+        assert!(super::find_package_dependency(
+            super::UnrenamedCrate {
+                name: Cow::Borrowed("fuchsia"),
+            },
+            super::RenamedCrate {
+                name: Cow::Borrowed("fuchsia"),
+            },
+            &semver::Version::parse("1.0.0").unwrap(),
+            deps.as_slice(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_denied_findings() {
+        let mut results = crate::test_util::sample_results();
+        // The mislabeled `barley` is only warned about.
+        assert_eq!(results.denied_findings(), 4);
+
+        let magenta = crate::test_util::package_id("magenta");
+        results
+            .workspace_members
+            .get_mut(&magenta)
+            .unwrap()
+            .levels
+            .override_deny(&[crate::LintCategory::Orphans]);
+        assert_eq!(results.denied_findings(), 2);
+
+        let levels: &mut crate::Levels =
+            &mut results.workspace_members.get_mut(&magenta).unwrap().levels;
+        levels.unused_normal = crate::Level::Allow;
+        levels.orphans = crate::Level::Allow;
+        results.remove_allowed_findings();
+        assert_eq!(results.unused_dependencies.len(), 2);
+        assert!(results.orphans.is_empty());
+        assert_eq!(results.denied_findings(), 1);
+
+        results.workspace_levels.unused_workspace = crate::Level::Allow;
+        results.remove_allowed_findings();
+        assert!(results.unused_workspace_dependencies.is_empty());
+        assert_eq!(results.denied_findings(), 0);
+    }
+
+    #[test]
+    fn test_mislabeled_dependencies() {
+        let mut results = crate::test_util::sample_results();
+        results
+            .mismarked_build_dependencies
+            .insert(crate::test_util::unused_dependency(
+                "magenta",
+                "fuchsia",
+                crate::DependencyKind::Normal,
+            ));
+        let mislabeled_deps: Vec<(&str, crate::DependencyKind)> = results
+            .mislabeled_dependencies()
+            .into_iter()
+            .map(|(mislabeled_dep, dep_kind)| {
+                return (mislabeled_dep.manifest_key.as_str(), dep_kind);
+            })
+            .collect();
+        assert_eq!(
+            mislabeled_deps,
+            [
+                ("barley", crate::DependencyKind::Development),
+                ("fuchsia", crate::DependencyKind::Build),
+            ]
+        );
+        assert_eq!(results.denied_findings(), 4);
+
+        let magenta = crate::test_util::package_id("magenta");
+        results
+            .workspace_members
+            .get_mut(&magenta)
+            .unwrap()
+            .levels
+            .mislabeled = crate::Level::Deny;
+        assert_eq!(results.denied_findings(), 6);
+
+        results
+            .workspace_members
+            .get_mut(&magenta)
+            .unwrap()
+            .levels
+            .mislabeled = crate::Level::Allow;
+        results.remove_allowed_findings();
+        assert!(results.mislabeled_dependencies().is_empty());
+        assert_eq!(results.denied_findings(), 4);
+    }
+
+    #[test]
+    fn test_remove_ignored_findings() {
+        let mut results = crate::test_util::sample_results();
+        let magenta = crate::test_util::package_id("magenta");
+        results.workspace_members.get_mut(&magenta).unwrap().ignored = vec![
+            crate::IgnoredDependency {
+                name: "fuchsia".to_owned(),
+                kind: None,
+                reason: "Used by macros".to_owned(),
+            },
+            crate::IgnoredDependency {
+                name: "purple".to_owned(),
+                kind: Some(crate::DependencyKind::Build),
+                reason: "Only used on some targets".to_owned(),
+            },
+        ];
+
+        let stale = results.remove_ignored_findings();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].0, magenta);
+        assert_eq!(stale[0].1.name, "purple");
+
+        assert_eq!(results.unused_dependencies.len(), 1);
+        assert_eq!(
+            results.unused_dependencies.iter().next().unwrap().dep_kind,
+            crate::DependencyKind::Development
+        );
+        assert_eq!(results.suppressed_dependencies.len(), 2);
+        for suppressed in results.suppressed_dependencies.iter() {
+            assert_eq!(suppressed.suppressed_by, crate::Suppression::Ignored);
+            assert_eq!(suppressed.reason.as_deref(), Some("Used by macros"));
+        }
+    }
+
+    #[test]
+    fn test_remove_ignored_findings_kept() {
+        let mut results = crate::test_util::sample_results();
+        let magenta = crate::test_util::package_id("magenta");
+        let purple: crate::UnusedDependency = crate::test_util::unused_dependency(
+            "magenta",
+            "purple",
+            crate::DependencyKind::Development,
+        );
+        results.unused_dependencies.remove(&purple);
+        results
+            .suppressed_dependencies
+            .insert(crate::SuppressedDependency {
+                unused_dependency: purple,
+                suppressed_by: crate::Suppression::KeepComment,
+                reason: None,
+            });
+        results.workspace_members.get_mut(&magenta).unwrap().ignored =
+            vec![crate::IgnoredDependency {
+                name: "purple".to_owned(),
+                kind: None,
+                reason: "Used by a macro".to_owned(),
+            }];
+
+        // The keep comment suppressed it first, but the entry still matches.
+        assert!(results.remove_ignored_findings().is_empty());
+        assert_eq!(results.suppressed_dependencies.len(), 1);
+        assert_eq!(
+            results
+                .suppressed_dependencies
+                .iter()
+                .next()
+                .unwrap()
+                .suppressed_by,
+            crate::Suppression::KeepComment
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use clap::Parser;
 
-fn main() {
+fn main() -> std::process::ExitCode {
     let args: reves::Args = reves::Args::parse();
-    reves::lib_main(&args);
+    return reves::lib_main(&args);
 }
@@ -0,0 +1,1810 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use anyhow::Context;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::DependencyKind;
+use crate::IgnoredDependency;
+use crate::SuppressedDependency;
+use crate::Suppression;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+/// A region of a manifest. Lines and columns are 1-based, and columns count
+/// `char`s (as `rustc` does). The end is exclusive.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize)]
+pub struct ManifestSpan {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+}
+
+fn line_column(text: &str, byte_offset: usize) -> (usize, usize) {
+    let before: &str = &text[..byte_offset];
+    let line: usize = before.matches('\n').count() + 1;
+    let line_offset: usize = before.rfind('\n').map_or(0, |i| {
+        return i + 1;
+    });
+    let column: usize = before[line_offset..].chars().count() + 1;
+    return (line, column);
+}
+
+impl ManifestSpan {
+    fn from_byte_range(text: &str, byte_start: usize, byte_end: usize) -> ManifestSpan {
+        let (line_start, column_start) = line_column(text, byte_start);
+        let (line_end, column_end) = line_column(text, byte_end);
+        return ManifestSpan {
+            byte_start,
+            byte_end,
+            line_start,
+            line_end,
+            column_start,
+            column_end,
+        };
+    }
+}
+
+pub(crate) fn table_like_mut<'a>(
+    document: &'a mut toml_edit::Document,
+    table_path: &[&str],
+) -> Option<&'a mut dyn toml_edit::TableLike> {
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for table_name in table_path.iter() {
+        table = table
+            .get_mut(table_name)
+            .and_then(toml_edit::Item::as_table_like_mut)?;
+    }
+    return Some(table);
+}
+
+// NUL is not allowed anywhere in a TOML document (including comments).
+const MARKER: &str = "\u{0}";
+
+/*
+  `toml_edit` (at least the versions supporting our MSRV) doesn't expose spans,
+  so instead a marker is appended to the decor directly before the key and the
+  document is rendered again. `toml_edit` preserves formatting so everything
+  before the marker is unchanged from the original text.
+*/
+fn key_span(
+    text: &str,
+    document: &toml_edit::Document,
+    table_path: &[&str],
+    key: &str,
+) -> anyhow::Result<Option<ManifestSpan>> {
+    let mut marked_document: toml_edit::Document = document.clone();
+    let table: &mut dyn toml_edit::TableLike =
+        match table_like_mut(&mut marked_document, table_path) {
+            Some(table) => table,
+            None => {
+                return Ok(None);
+            }
+        };
+
+    let key_len: usize = match table.get_key_value(key) {
+        Some((key, _)) => key.display_repr().len(),
+        None => {
+            return Ok(None);
+        }
+    };
+    let decor: &mut toml_edit::Decor = table.key_decor_mut(key).unwrap();
+    let prefix: String = match decor.prefix().and_then(toml_edit::RawString::as_str) {
+        Some(prefix) => prefix.to_owned(),
+        None => {
+            // The default decor isn't known to us, so we can't tell how it will
+            // be rendered.
+            return Ok(None);
+        }
+    };
+    decor.set_prefix(prefix + MARKER);
+
+    let marked_text: String = marked_document.to_string();
+    let byte_start: usize = marked_text
+        .find(MARKER)
+        .expect("marker missing from rendered manifest");
+    anyhow::ensure!(
+        text.get(..byte_start) == Some(&marked_text[..byte_start]),
+        "manifest does not round trip through toml_edit"
+    );
+    return Ok(Some(ManifestSpan::from_byte_range(
+        text,
+        byte_start,
+        byte_start + key_len,
+    )));
+}
+
+/// A table of dependencies within a manifest, such as `[dev-dependencies]`, or
+/// `[target.'cfg(unix)'.dependencies]`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct DependencyTable {
+    pub(crate) dep_kind: DependencyKind,
+    /// The `cfg(...)` expression or target triple for platform specific tables.
+    pub(crate) target: Option<String>,
+}
+
+impl DependencyTable {
+    pub(crate) fn path(&self) -> Vec<&str> {
+        let mut path = Vec::<&str>::with_capacity(3);
+        if let Some(target) = self.target.as_ref() {
+            path.push("target");
+            path.push(target.as_str());
+        }
+        path.push(dep_kind_to_toml_key(self.dep_kind));
+        return path;
+    }
+
+    /// Whether this is the table for `target` (as in
+    /// `UnusedDependency::targets`).
+    pub(crate) fn is_for_target(&self, target: Option<&str>) -> bool {
+        return match (self.target.as_deref(), target) {
+            (None, None) => true,
+            (Some(table_target), Some(target)) => is_same_target(table_target, target),
+            _ => false,
+        };
+    }
+}
+
+/// `cargo` normalizes the whitespace of `cfg(...)` expressions, so they're
+/// compared without any whitespace.
+fn is_same_target(a: &str, b: &str) -> bool {
+    return a
+        .chars()
+        .filter(|c| {
+            return !c.is_whitespace();
+        })
+        .eq(b.chars().filter(|c| {
+            return !c.is_whitespace();
+        }));
+}
+
+pub(crate) fn dep_kind_to_toml_key(dep_kind: DependencyKind) -> &'static str {
+    return match dep_kind {
+        DependencyKind::Normal => "dependencies",
+        DependencyKind::Development => "dev-dependencies",
+        DependencyKind::Build => "build-dependencies",
+    };
+}
+
+/// Lists all dependency tables in the order in which they are declared,
+/// top-level tables first. The legacy `dev_dependencies`, and
+/// `build_dependencies` spellings are not supported.
+pub(crate) fn dependency_tables(document: &toml_edit::Document) -> Vec<DependencyTable> {
+    let mut tables = Vec::<DependencyTable>::new();
+    for (name, item) in document.iter() {
+        if let Some(dep_kind) = crate::toml_key_to_dep_kind(name) {
+            if item.is_table_like() {
+                tables.push(DependencyTable {
+                    dep_kind,
+                    target: None,
+                });
+            }
+        }
+    }
+    if let Some(targets) = document
+        .get("target")
+        .and_then(toml_edit::Item::as_table_like)
+    {
+        for (target, target_item) in targets.iter() {
+            if let Some(target_table) = target_item.as_table_like() {
+                for (name, item) in target_table.iter() {
+                    if let Some(dep_kind) = crate::toml_key_to_dep_kind(name) {
+                        if item.is_table_like() {
+                            tables.push(DependencyTable {
+                                dep_kind,
+                                target: Some(target.to_owned()),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    return tables;
+}
+
+/// Finds the key of `key` within the first `dep_kind` table for one of
+/// `targets` (from `UnusedDependency::targets`) which contains it, preferring
+/// the top-level table over platform specific tables. If `targets` is empty
+/// every `dep_kind` table is considered.
+pub(crate) fn find_dependency_span(
+    text: &str,
+    dep_kind: DependencyKind,
+    targets: &[Option<String>],
+    key: &str,
+) -> anyhow::Result<Option<ManifestSpan>> {
+    let document = toml_edit::Document::from_str(text)?;
+    for table in dependency_tables(&document).iter() {
+        if table.dep_kind != dep_kind {
+            continue;
+        }
+        let is_declared: bool = targets.is_empty()
+            || targets.iter().any(|target| {
+                return table.is_for_target(target.as_deref());
+            });
+        if !is_declared {
+            continue;
+        }
+        if let Some(span) = key_span(text, &document, table.path().as_slice(), key)? {
+            return Ok(Some(span));
+        }
+    }
+    return Ok(None);
+}
+
+pub(crate) fn table_like<'a>(
+    document: &'a toml_edit::Document,
+    table_path: &[&str],
+) -> Option<&'a dyn toml_edit::TableLike> {
+    let mut table: &dyn toml_edit::TableLike = document.as_table();
+    for table_name in table_path.iter() {
+        table = table
+            .get(table_name)
+            .and_then(toml_edit::Item::as_table_like)?;
+    }
+    return Some(table);
+}
+
+const KEEP_MARKER: &str = "reves: keep";
+
+/// A `# reves: keep` comment, optionally followed by `-- reason`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct KeepComment {
+    pub(crate) reason: Option<String>,
+}
+
+fn parse_keep_comment(decor: &str) -> Option<KeepComment> {
+    for line in decor.lines() {
+        let comment: &str = match line.trim_start().strip_prefix('#') {
+            Some(comment) => comment.trim(),
+            None => {
+                continue;
+            }
+        };
+        let rest: &str = match comment.strip_prefix(KEEP_MARKER) {
+            Some(rest) => rest.trim(),
+            None => {
+                continue;
+            }
+        };
+        if rest.is_empty() {
+            return Some(KeepComment { reason: None });
+        }
+        if let Some(reason) = rest.strip_prefix("--") {
+            let reason: &str = reason.trim();
+            return Some(KeepComment {
+                reason: if reason.is_empty() {
+                    None
+                } else {
+                    Some(reason.to_owned())
+                },
+            });
+        }
+    }
+    return None;
+}
+
+fn decor_comments<'a>(decor: &'a toml_edit::Decor, comments: &mut Vec<&'a str>) {
+    for raw in [decor.prefix(), decor.suffix()].iter().flatten() {
+        if let Some(raw) = raw.as_str() {
+            comments.push(raw);
+        }
+    }
+}
+
+/// Collects the comments within an entry, such as trailing comments, and the
+/// comments of the keys of `[dependencies.key]` tables.
+fn item_comments<'a>(item: &'a toml_edit::Item, comments: &mut Vec<&'a str>) {
+    match item {
+        toml_edit::Item::Value(value) => {
+            decor_comments(value.decor(), comments);
+            if let Some(inline_table) = value.as_inline_table() {
+                for (key, _) in inline_table.iter() {
+                    decor_comments(inline_table.key_decor(key).unwrap(), comments);
+                }
+                for (_, child) in inline_table.iter() {
+                    decor_comments(child.decor(), comments);
+                }
+            }
+        }
+        toml_edit::Item::Table(table) => {
+            decor_comments(table.decor(), comments);
+            for (key, child) in table.iter() {
+                decor_comments(table.key_decor(key).unwrap(), comments);
+                item_comments(child, comments);
+            }
+        }
+        toml_edit::Item::None | toml_edit::Item::ArrayOfTables(_) => {}
+    }
+}
+
+/// Finds a keep comment attached to the entry (found the same way as
+/// [`find_dependency_span`]) for `key`, either on the lines directly above it,
+/// or trailing any of its lines.
+pub(crate) fn find_keep_comment(
+    text: &str,
+    dep_kind: DependencyKind,
+    targets: &[Option<String>],
+    key: &str,
+) -> anyhow::Result<Option<KeepComment>> {
+    let document = toml_edit::Document::from_str(text)?;
+    for table in dependency_tables(&document).iter() {
+        if table.dep_kind != dep_kind {
+            continue;
+        }
+        let is_declared: bool = targets.is_empty()
+            || targets.iter().any(|target| {
+                return table.is_for_target(target.as_deref());
+            });
+        if !is_declared {
+            continue;
+        }
+        let table_like: &dyn toml_edit::TableLike = match table_like(&document, &table.path()) {
+            Some(table_like) => table_like,
+            None => {
+                continue;
+            }
+        };
+        if let Some(item) = table_like.get(key) {
+            let mut comments = Vec::<&str>::new();
+            decor_comments(table_like.key_decor(key).unwrap(), &mut comments);
+            item_comments(item, &mut comments);
+            for comment in comments.into_iter() {
+                if let Some(keep_comment) = parse_keep_comment(comment) {
+                    return Ok(Some(keep_comment));
+                }
+            }
+            return Ok(None);
+        }
+    }
+    return Ok(None);
+}
+
+/// A replacement of part of a manifest.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ManifestEdit {
+    pub(crate) span: ManifestSpan,
+    pub(crate) replacement: String,
+}
+
+/// Computes the smallest edit which turns `old_text` into `new_text`.
+fn minimal_edit(old_text: &str, new_text: &str) -> ManifestEdit {
+    let mut prefix_len: usize = 0;
+    for (old_byte, new_byte) in old_text.bytes().zip(new_text.bytes()) {
+        if old_byte != new_byte {
+            break;
+        }
+        prefix_len += 1;
+    }
+    while !old_text.is_char_boundary(prefix_len) || !new_text.is_char_boundary(prefix_len) {
+        prefix_len -= 1;
+    }
+
+    let max_suffix_len: usize = std::cmp::min(old_text.len(), new_text.len()) - prefix_len;
+    let mut suffix_len: usize = 0;
+    for (old_byte, new_byte) in old_text.bytes().rev().zip(new_text.bytes().rev()) {
+        if suffix_len == max_suffix_len || old_byte != new_byte {
+            break;
+        }
+        suffix_len += 1;
+    }
+    while !old_text.is_char_boundary(old_text.len() - suffix_len)
+        || !new_text.is_char_boundary(new_text.len() - suffix_len)
+    {
+        suffix_len -= 1;
+    }
+
+    // A removal is ambiguous if the text before it matches the end of the
+    // removed text, so prefer the position which starts on a new line.
+    if old_text.len() - suffix_len - prefix_len > 0 && new_text.len() == prefix_len + suffix_len {
+        let old_bytes: &[u8] = old_text.as_bytes();
+        while prefix_len > 0
+            && old_bytes[prefix_len - 1] != b'\n'
+            && old_bytes[prefix_len - 1] == old_bytes[old_text.len() - suffix_len - 1]
+            && old_text.is_char_boundary(prefix_len - 1)
+        {
+            prefix_len -= 1;
+            suffix_len += 1;
+        }
+    }
+
+    return ManifestEdit {
+        span: ManifestSpan::from_byte_range(old_text, prefix_len, old_text.len() - suffix_len),
+        replacement: new_text[prefix_len..new_text.len() - suffix_len].to_owned(),
+    };
+}
+
+/// The edit removing the entry (found the same way as [`find_dependency_span`])
+/// for `key`, including its comments and any `[dependencies.key]` table.
+pub(crate) fn dependency_removal(
+    text: &str,
+    dep_kind: DependencyKind,
+    targets: &[Option<String>],
+    key: &str,
+) -> anyhow::Result<Option<ManifestEdit>> {
+    let document = toml_edit::Document::from_str(text)?;
+    for table in dependency_tables(&document).iter() {
+        if table.dep_kind != dep_kind {
+            continue;
+        }
+        let is_declared: bool = targets.is_empty()
+            || targets.iter().any(|target| {
+                return table.is_for_target(target.as_deref());
+            });
+        if !is_declared {
+            continue;
+        }
+        let mut edited_document: toml_edit::Document = document.clone();
+        if let Some(table_like) = table_like_mut(&mut edited_document, &table.path()) {
+            if table_like.remove(key).is_some() {
+                return Ok(Some(minimal_edit(text, &edited_document.to_string())));
+            }
+        }
+    }
+    return Ok(None);
+}
+
+/// Removes `key` from each `dep_kind` table for one of `targets` (from
+/// `UnusedDependency::targets`), returning the number of removed entries. If
+/// `targets` is empty every `dep_kind` table is considered.
+pub(crate) fn remove_dependency(
+    document: &mut toml_edit::Document,
+    dep_kind: DependencyKind,
+    targets: &[Option<String>],
+    key: &str,
+) -> usize {
+    let mut removed: usize = 0;
+    for table in dependency_tables(document).iter() {
+        if table.dep_kind != dep_kind {
+            continue;
+        }
+        let is_declared: bool = targets.is_empty()
+            || targets.iter().any(|target| {
+                return table.is_for_target(target.as_deref());
+            });
+        if !is_declared {
+            continue;
+        }
+        if let Some(table_like) = table_like_mut(document, &table.path()) {
+            if table_like.remove(key).is_some() {
+                removed += 1;
+            }
+        }
+    }
+    return removed;
+}
+
+/// The value of `value` without its formatting, and comments.
+fn normalized_value(value: &toml_edit::Value) -> String {
+    return match value {
+        toml_edit::Value::String(string) => format!("{:?}", string.value()),
+        toml_edit::Value::Array(array) => {
+            let values: Vec<String> = array.iter().map(normalized_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        toml_edit::Value::InlineTable(inline_table) => {
+            let mut entries = Vec::<String>::new();
+            for (key, value) in inline_table.iter() {
+                entries.push(format!("{} = {}", key, normalized_value(value)));
+            }
+            entries.sort();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        _ => {
+            let mut value: toml_edit::Value = value.clone();
+            value.decor_mut().clear();
+            value.to_string()
+        }
+    };
+}
+
+/// The spec of a dependency entry as its keys, and normalized values, so that
+/// equivalent specs (such as `"1.0"`, and `{ version = "1.0" }`) are equal.
+fn normalized_spec(item: &toml_edit::Item) -> Option<BTreeMap<String, String>> {
+    if let Some(version) = item.as_str() {
+        return Some(BTreeMap::from([(
+            "version".to_owned(),
+            format!("{:?}", version),
+        )]));
+    }
+    let mut spec = BTreeMap::<String, String>::new();
+    for (key, child) in item.as_table_like()?.iter() {
+        spec.insert(key.to_owned(), normalized_value(child.as_value()?));
+    }
+    return Some(spec);
+}
+
+/// The entries moved by [`move_dependency`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct MovedEntries {
+    pub(crate) moved: usize,
+    /// Entries which were left in place, as the destination table already
+    /// declares the key with a different spec.
+    pub(crate) conflicts: usize,
+}
+
+/// Moves `key` from each `[dependencies]` table for one of `targets` (from
+/// `UnusedDependency::targets`) to the `dep_kind` table for the same platform
+/// (such as `[dev-dependencies]`), keeping its spec and comments. If the
+/// `dep_kind` table already declares `key` the entry is only removed if both
+/// specs are equivalent, and otherwise it's left in place as a conflict.
+pub(crate) fn move_dependency(
+    document: &mut toml_edit::Document,
+    targets: &[Option<String>],
+    key: &str,
+    dep_kind: DependencyKind,
+) -> anyhow::Result<MovedEntries> {
+    let mut moved_entries = MovedEntries::default();
+    for table in dependency_tables(document).iter() {
+        if table.dep_kind != DependencyKind::Normal {
+            continue;
+        }
+        let is_declared: bool = targets.is_empty()
+            || targets.iter().any(|target| {
+                return table.is_for_target(target.as_deref());
+            });
+        if !is_declared {
+            continue;
+        }
+        let destination = DependencyTable {
+            dep_kind,
+            target: table.target.clone(),
+        };
+        let existing_spec: Option<Option<BTreeMap<String, String>>> =
+            table_like(document, &destination.path())
+                .and_then(|destination| {
+                    return destination.get(key);
+                })
+                .map(normalized_spec);
+        let table_like: &mut dyn toml_edit::TableLike =
+            match table_like_mut(document, &table.path()) {
+                Some(table_like) => table_like,
+                None => continue,
+            };
+        // The key is kept for its decor (such as comments on the line above).
+        let moved_key: toml_edit::Key = match table_like.get_key_value(key) {
+            Some((moved_key, item)) => {
+                if let Some(existing_spec) = existing_spec.as_ref() {
+                    if existing_spec.is_none() || *existing_spec != normalized_spec(item) {
+                        moved_entries.conflicts += 1;
+                        continue;
+                    }
+                }
+                moved_key.clone()
+            }
+            None => continue,
+        };
+        let item: toml_edit::Item = table_like.remove(key).unwrap();
+        moved_entries.moved += 1;
+        if existing_spec.is_some() {
+            continue;
+        }
+        let parent: &mut toml_edit::Table = match table.target.as_ref() {
+            Some(target) => table_entry(
+                table_entry(document.as_table_mut(), "target")?,
+                target.as_str(),
+            )?,
+            None => document.as_table_mut(),
+        };
+        table_entry(parent, dep_kind_to_toml_key(dep_kind))?.insert_formatted(&moved_key, item);
+    }
+    return Ok(moved_entries);
+}
+
+/// Whether any dependency table of one of `dep_kinds` (for any platform)
+/// declares `key`.
+pub(crate) fn is_declared_by(
+    document: &toml_edit::Document,
+    dep_kinds: &[DependencyKind],
+    key: &str,
+) -> bool {
+    for table in dependency_tables(document).iter() {
+        if !dep_kinds.contains(&table.dep_kind) {
+            continue;
+        }
+        if let Some(table_like) = table_like(document, &table.path()) {
+            if table_like.contains_key(key) {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+/// Whether the `[features]` value `feature_value` refers to the dependency
+/// `key`, either enabling it (`dep:key`) or one of its features (`key/feature`,
+/// or `key?/feature`). The implicit feature `key` is handled separately as it
+/// is indistinguishable from a feature named `key`.
+fn is_dependency_reference(feature_value: &str, key: &str) -> bool {
+    if feature_value.strip_prefix("dep:") == Some(key) {
+        return true;
+    }
+    return match feature_value.split_once('/') {
+        Some((dependency, _)) => dependency.trim_end_matches('?') == key,
+        None => false,
+    };
+}
+
+/// Removes the values of each feature for which `is_removed` holds, returning
+/// the number of removed values, and the names of the features left empty.
+fn remove_feature_values(
+    features: &mut dyn toml_edit::TableLike,
+    is_removed: &dyn Fn(&str) -> bool,
+) -> (usize, Vec<String>) {
+    let mut removed: usize = 0;
+    let mut emptied = Vec::<String>::new();
+    for (feature, item) in features.iter_mut() {
+        let values: &mut toml_edit::Array = match item.as_array_mut() {
+            Some(values) => values,
+            None => {
+                continue;
+            }
+        };
+        let had_values: bool = !values.is_empty();
+        // Kept so a single-line array keeps its formatting when its first
+        // value is removed.
+        let first_prefix: Option<String> = values.get(0).and_then(|value| {
+            return value.decor().prefix().and_then(|prefix| {
+                return prefix.as_str().map(str::to_owned);
+            });
+        });
+        for index in (0..values.len()).rev() {
+            let is_value_removed: bool = match values.get(index).and_then(toml_edit::Value::as_str)
+            {
+                Some(value) => is_removed(value),
+                None => false,
+            };
+            if is_value_removed {
+                values.remove(index);
+                removed += 1;
+            }
+        }
+        if let (Some(first), Some(first_prefix)) = (values.get_mut(0), first_prefix) {
+            first.decor_mut().set_prefix(first_prefix);
+        }
+        if had_values && values.is_empty() {
+            // Any whitespace or comments were for the removed values.
+            values.set_trailing("");
+            values.set_trailing_comma(false);
+            emptied.push(feature.get().to_owned());
+        }
+    }
+    return (removed, emptied);
+}
+
+/// Removes references to the dependency `key`, which is no longer declared,
+/// from `[features]` so the manifest stays valid. A feature named `key` (such
+/// as `key = ["dep:key"]`, written out in place of the implicit feature) which
+/// is left empty is removed along with references to it, other emptied
+/// features are kept as they may be enabled by dependants. Returns the number
+/// of removed feature values, and features.
+pub(crate) fn remove_feature_references(document: &mut toml_edit::Document, key: &str) -> usize {
+    let features: &mut dyn toml_edit::TableLike = match table_like_mut(document, &["features"]) {
+        Some(features) => features,
+        None => {
+            return 0;
+        }
+    };
+    let has_key_feature: bool = features.contains_key(key);
+    let (mut removed, emptied): (usize, Vec<String>) =
+        remove_feature_values(features, &|feature_value: &str| {
+            return is_dependency_reference(feature_value, key)
+                // Otherwise `key` is the implicit feature of the dependency.
+                || (!has_key_feature && feature_value == key);
+        });
+    if emptied.iter().any(|feature| {
+        return feature == key;
+    }) {
+        features.remove(key);
+        let (removed_references, _): (usize, Vec<String>) =
+            remove_feature_values(features, &|feature_value: &str| {
+                return feature_value == key;
+            });
+        removed += 1 + removed_references;
+    }
+    return removed;
+}
+
+const WORKSPACE_DEPENDENCIES_PATH: [&str; 2] = ["workspace", "dependencies"];
+
+/// The keys of `[workspace.dependencies]`, in the order they are declared.
+pub(crate) fn workspace_dependency_keys(document: &toml_edit::Document) -> Vec<String> {
+    return match table_like(document, &WORKSPACE_DEPENDENCIES_PATH) {
+        Some(table_like) => table_like
+            .iter()
+            .map(|(key, _)| {
+                return key.to_owned();
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+}
+
+/// The keys of the dependencies inherited from `[workspace.dependencies]` (as
+/// in `key.workspace = true`) by any table of a member's manifest.
+pub(crate) fn inherited_dependency_keys(document: &toml_edit::Document) -> BTreeSet<String> {
+    let mut keys = BTreeSet::<String>::new();
+    for table in dependency_tables(document).iter() {
+        let table_like: &dyn toml_edit::TableLike = match table_like(document, &table.path()) {
+            Some(table_like) => table_like,
+            None => {
+                continue;
+            }
+        };
+        for (key, item) in table_like.iter() {
+            let is_inherited: bool = item
+                .as_table_like()
+                .and_then(|dependency| {
+                    return dependency.get("workspace");
+                })
+                .and_then(toml_edit::Item::as_bool)
+                == Some(true);
+            if is_inherited {
+                keys.insert(key.to_owned());
+            }
+        }
+    }
+    return keys;
+}
+
+/// Finds the key of `key` within `[workspace.dependencies]`.
+pub(crate) fn find_workspace_dependency_span(
+    text: &str,
+    key: &str,
+) -> anyhow::Result<Option<ManifestSpan>> {
+    let document = toml_edit::Document::from_str(text)?;
+    return key_span(text, &document, &WORKSPACE_DEPENDENCIES_PATH, key);
+}
+
+/// Removes `key` from `[workspace.dependencies]`, returning whether it was
+/// declared.
+pub(crate) fn remove_workspace_dependency(document: &mut toml_edit::Document, key: &str) -> bool {
+    return match table_like_mut(document, &WORKSPACE_DEPENDENCIES_PATH) {
+        Some(table_like) => table_like.remove(key).is_some(),
+        None => false,
+    };
+}
+
+/// The table `key` of `table`, inserted (without a header of its own) if it's
+/// missing.
+pub(crate) fn table_entry<'a>(
+    table: &'a mut toml_edit::Table,
+    key: &str,
+) -> anyhow::Result<&'a mut toml_edit::Table> {
+    return table
+        .entry(key)
+        .or_insert_with(|| {
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(true);
+            return toml_edit::Item::Table(new_table);
+        })
+        .as_table_mut()
+        .with_context(|| format!("`{}` isn't a table", key));
+}
+
+/// Appends `ignored` to the `ignored` array of `[package.metadata.reves]`,
+/// creating it if needed. Entries are written one per line, unless the array
+/// is already on a single line.
+pub(crate) fn add_ignored_dependency(
+    document: &mut toml_edit::Document,
+    ignored: &IgnoredDependency,
+) -> anyhow::Result<()> {
+    let package: &mut toml_edit::Table = document
+        .as_table_mut()
+        .get_mut("package")
+        .and_then(toml_edit::Item::as_table_mut)
+        .context("The manifest has no [package] table")?;
+    let reves: &mut toml_edit::Table = table_entry(table_entry(package, "metadata")?, "reves")?;
+    let ignored_array: &mut toml_edit::Array = reves
+        .entry("ignored")
+        .or_insert_with(|| {
+            return toml_edit::value(toml_edit::Array::new());
+        })
+        .as_array_mut()
+        .context("[package.metadata.reves] `ignored` isn't an array")?;
+
+    let mut entry = toml_edit::InlineTable::new();
+    entry.insert("name", ignored.name.as_str().into());
+    if let Some(kind) = ignored.kind {
+        entry.insert("kind", kind.serde_name().into());
+    }
+    entry.insert("reason", ignored.reason.as_str().into());
+    let is_multiline: bool = match ignored_array.iter().last() {
+        Some(last) => match last.decor().prefix().and_then(toml_edit::RawString::as_str) {
+            Some(prefix) => prefix.contains('\n'),
+            None => false,
+        },
+        None => true,
+    };
+    ignored_array.push(entry);
+    if is_multiline {
+        ignored_array
+            .iter_mut()
+            .last()
+            .unwrap()
+            .decor_mut()
+            .set_prefix("\n  ");
+        ignored_array.set_trailing_comma(true);
+        ignored_array.set_trailing("\n");
+    }
+    return Ok(());
+}
+
+pub(crate) fn read_manifest(
+    manifest_path: &Utf8Path,
+) -> anyhow::Result<(String, toml_edit::Document)> {
+    let text: String = std::fs::read_to_string(manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path))?;
+    let document = toml_edit::Document::from_str(text.as_str())
+        .with_context(|| format!("Failed to parse {}", manifest_path))?;
+    return Ok((text, document));
+}
+
+/// Finds the `[workspace.dependencies]` entries of `root_manifest_path` which
+/// none of `member_manifest_paths` inherit.
+pub(crate) fn find_unused_workspace_dependencies(
+    root_manifest_path: &Utf8Path,
+    member_manifest_paths: &BTreeSet<Utf8PathBuf>,
+) -> anyhow::Result<HashSet<UnusedWorkspaceDependency>> {
+    let (root_text, root_document) = read_manifest(root_manifest_path)?;
+    let keys: Vec<String> = workspace_dependency_keys(&root_document);
+    if keys.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let mut inherited_keys = BTreeSet::<String>::new();
+    for manifest_path in member_manifest_paths.iter() {
+        let (_, document) = read_manifest(manifest_path)?;
+        inherited_keys.append(&mut inherited_dependency_keys(&document));
+    }
+
+    let mut unused = HashSet::<UnusedWorkspaceDependency>::new();
+    for key in keys.into_iter() {
+        if inherited_keys.contains(&key) {
+            continue;
+        }
+        unused.insert(UnusedWorkspaceDependency {
+            manifest_span: find_workspace_dependency_span(root_text.as_str(), key.as_str())?,
+            key,
+            manifest_path: root_manifest_path.to_owned(),
+        });
+    }
+    return Ok(unused);
+}
+
+/// Fills in `manifest_span` for each dependency, reading each manifest once.
+pub(crate) fn add_manifest_spans(
+    unused_deps: HashSet<UnusedDependency>,
+) -> anyhow::Result<HashSet<UnusedDependency>> {
+    let mut manifests = HashMap::<Utf8PathBuf, String>::new();
+    let mut spanned_unused_deps = HashSet::<UnusedDependency>::with_capacity(unused_deps.len());
+    for mut unused_dep in unused_deps.into_iter() {
+        if !manifests.contains_key(&unused_dep.dependant_manifest_path) {
+            let manifest_text: String =
+                std::fs::read_to_string(&unused_dep.dependant_manifest_path)?;
+            manifests.insert(unused_dep.dependant_manifest_path.clone(), manifest_text);
+        }
+        unused_dep.manifest_span = find_dependency_span(
+            manifests[&unused_dep.dependant_manifest_path].as_str(),
+            unused_dep.dep_kind,
+            unused_dep.targets.as_slice(),
+            unused_dep.manifest_key.as_str(),
+        )
+        .with_context(|| {
+            return format!("Failed to parse {}", unused_dep.dependant_manifest_path);
+        })?;
+        spanned_unused_deps.insert(unused_dep);
+    }
+    return Ok(spanned_unused_deps);
+}
+
+/// Moves dependencies with a keep comment from `unused_deps` to the returned
+/// suppressed dependencies.
+pub(crate) fn remove_kept_dependencies(
+    unused_deps: &mut HashSet<UnusedDependency>,
+) -> anyhow::Result<HashSet<SuppressedDependency>> {
+    let mut manifests = HashMap::<Utf8PathBuf, String>::new();
+    let mut kept = HashSet::<SuppressedDependency>::new();
+    for unused_dep in unused_deps.iter() {
+        if !manifests.contains_key(&unused_dep.dependant_manifest_path) {
+            let manifest_text: String =
+                std::fs::read_to_string(&unused_dep.dependant_manifest_path)?;
+            manifests.insert(unused_dep.dependant_manifest_path.clone(), manifest_text);
+        }
+        let keep_comment: Option<KeepComment> = find_keep_comment(
+            manifests[&unused_dep.dependant_manifest_path].as_str(),
+            unused_dep.dep_kind,
+            unused_dep.targets.as_slice(),
+            unused_dep.manifest_key.as_str(),
+        )
+        .with_context(|| {
+            return format!("Failed to parse {}", unused_dep.dependant_manifest_path);
+        })?;
+        if let Some(keep_comment) = keep_comment {
+            kept.insert(SuppressedDependency {
+                unused_dependency: unused_dep.clone(),
+                suppressed_by: Suppression::KeepComment,
+                reason: keep_comment.reason,
+            });
+        }
+    }
+    for suppressed in kept.iter() {
+        unused_deps.remove(&suppressed.unused_dependency);
+    }
+    return Ok(kept);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+    use std::str::FromStr;
+
+    use camino::Utf8PathBuf;
+
+    use crate::DependencyKind;
+    use crate::IgnoredDependency;
+    use crate::UnusedWorkspaceDependency;
+
+    struct SpanTest {
+        manifest: &'static str,
+        dep_kind: DependencyKind,
+        targets: &'static [Option<&'static str>],
+        key: &'static str,
+        // (line, column) of the start and end of the key
+        expected: Option<((usize, usize), (usize, usize))>,
+    }
+
+    #[test]
+    fn test_dependency_removal() {
+        let manifest: &str = concat!(
+            "[dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "# The colour of the sky.\n",
+            "vache.workspace = true\n",
+            "purple = { path = \"../purple\" } # trailing\n",
+            "\n",
+            "[dev-dependencies.purple]\n",
+            "path = \"../purple\"\n",
+            "\n",
+            "[build-dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+        );
+        let removal_tests: &[(DependencyKind, &str, Option<&str>)] = &[
+            (
+                DependencyKind::Normal,
+                "fuchsia",
+                Some("fuchsia = \"1.0\"\n"),
+            ),
+            (
+                DependencyKind::Normal,
+                "vache",
+                Some("# The colour of the sky.\nvache.workspace = true\n"),
+            ),
+            (
+                DependencyKind::Normal,
+                "purple",
+                Some("purple = { path = \"../purple\" } # trailing\n"),
+            ),
+            (
+                DependencyKind::Development,
+                "purple",
+                Some("[dev-dependencies.purple]\npath = \"../purple\"\n\n"),
+            ),
+            (DependencyKind::Development, "fuchsia", None),
+        ];
+        for (dep_kind, key, removed) in removal_tests.iter() {
+            let edit: Option<super::ManifestEdit> =
+                super::dependency_removal(manifest, *dep_kind, &[], key).unwrap();
+            if let Some(edit) = edit.as_ref() {
+                assert_eq!(edit.replacement, "", "{}", key);
+            }
+            assert_eq!(
+                edit.map(|edit| {
+                    return &manifest[edit.span.byte_start..edit.span.byte_end];
+                }),
+                *removed,
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_dependency_span() {
+        let manifest: &str = concat!(
+            "[package]\n",
+            "name = \"magenta\"\n",
+            "\n",
+            "[dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "  vache.workspace = true # a comment\n",
+            "\"quoted\" = { path = \"../quoted\" }\n",
+            "\n",
+            "[dev-dependencies.purple]\n",
+            "path = \"../purple\"\n",
+            "\n",
+            "[build-dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "\n",
+            "[target.'cfg(unix)'.dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "unix-only = \"1.0\"\n",
+            "\n",
+            "[target.x86_64-pc-windows-msvc.build-dependencies]\n",
+            "windows-only = \"1.0\"\n",
+        );
+        let span_tests: &[SpanTest] = &[
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[],
+                key: "fuchsia",
+                expected: Some(((5, 1), (5, 8))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[],
+                key: "vache",
+                expected: Some(((6, 3), (6, 8))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[],
+                key: "quoted",
+                expected: Some(((7, 1), (7, 9))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Development,
+                targets: &[],
+                key: "purple",
+                expected: Some(((9, 19), (9, 25))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Build,
+                targets: &[],
+                key: "fuchsia",
+                expected: Some(((13, 1), (13, 8))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Development,
+                targets: &[],
+                key: "fuchsia",
+                expected: None,
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[Some("cfg(unix)")],
+                key: "fuchsia",
+                expected: Some(((16, 1), (16, 8))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[None],
+                key: "fuchsia",
+                expected: Some(((5, 1), (5, 8))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[Some("cfg(windows)")],
+                key: "fuchsia",
+                expected: None,
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[],
+                key: "unix-only",
+                expected: Some(((17, 1), (17, 10))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Build,
+                targets: &[],
+                key: "windows-only",
+                expected: Some(((20, 1), (20, 13))),
+            },
+            SpanTest {
+                manifest,
+                dep_kind: DependencyKind::Normal,
+                targets: &[],
+                key: "windows-only",
+                expected: None,
+            },
+        ];
+
+        for span_test in span_tests.iter() {
+            let targets: Vec<Option<String>> = span_test
+                .targets
+                .iter()
+                .map(|target| {
+                    return target.map(str::to_owned);
+                })
+                .collect();
+            let span: Option<super::ManifestSpan> = super::find_dependency_span(
+                span_test.manifest,
+                span_test.dep_kind,
+                targets.as_slice(),
+                span_test.key,
+            )
+            .unwrap();
+            assert_eq!(
+                span.map(|span| {
+                    return (
+                        (span.line_start, span.column_start),
+                        (span.line_end, span.column_end),
+                    );
+                }),
+                span_test.expected,
+                "{}",
+                span_test.key
+            );
+            if let Some(span) = span {
+                assert!(span_test.manifest[span.byte_start..span.byte_end].contains(span_test.key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_keep_comment() {
+        let manifest: &str = concat!(
+            "[dependencies]\n",
+            "backtrace = \"0.3.51\" # reves: keep -- version floor\n",
+            "# reves: keep\n",
+            "fuchsia = \"1.0\"\n",
+            "magenta = { version = \"1.0\" } # reves: keeper\n",
+            "vache.version = \"1.0\" # reves: keep --\n",
+            "# Not reves: keep\n",
+            "purple = \"1.0\"\n",
+            "\n",
+            "[dev-dependencies.purple] # reves: keep -- used by doc tests\n",
+            "path = \"../purple\"\n",
+            "\n",
+            "[target.'cfg(unix)'.build-dependencies]\n",
+            "fuchsia = { path = \"../fuchsia\" } # reves: keep -- build script on unix\n",
+        );
+        let keep_tests: &[(DependencyKind, &str, Option<Option<&str>>)] = &[
+            (
+                DependencyKind::Normal,
+                "backtrace",
+                Some(Some("version floor")),
+            ),
+            (DependencyKind::Normal, "fuchsia", Some(None)),
+            (DependencyKind::Normal, "magenta", None),
+            (DependencyKind::Normal, "vache", Some(None)),
+            (DependencyKind::Normal, "purple", None),
+            (DependencyKind::Normal, "missing", None),
+            (
+                DependencyKind::Development,
+                "purple",
+                Some(Some("used by doc tests")),
+            ),
+            (
+                DependencyKind::Build,
+                "fuchsia",
+                Some(Some("build script on unix")),
+            ),
+        ];
+        for (dep_kind, key, expected) in keep_tests.iter() {
+            assert_eq!(
+                super::find_keep_comment(manifest, *dep_kind, &[], key).unwrap(),
+                expected.map(|reason| {
+                    return super::KeepComment {
+                        reason: reason.map(str::to_owned),
+                    };
+                }),
+                "{:?} {}",
+                dep_kind,
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_remove_dependency() {
+        let manifest: &str = concat!(
+            "[dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "\n",
+            "[target.'cfg(unix)'.dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+            "purple = \"1.0\"\n",
+            "\n",
+            "[target.'cfg(any(windows,target_os = \"macos\"))'.dependencies]\n",
+            "purple = \"1.0\"\n",
+            "\n",
+            "[target.x86_64-pc-windows-gnu.build-dependencies]\n",
+            "fuchsia = \"1.0\"\n",
+        );
+        struct RemoveTest {
+            dep_kind: DependencyKind,
+            targets: &'static [Option<&'static str>],
+            key: &'static str,
+            removed: usize,
+            expected: &'static str,
+        }
+        let remove_tests: &[RemoveTest] = &[
+            RemoveTest {
+                dep_kind: DependencyKind::Normal,
+                targets: &[None],
+                key: "fuchsia",
+                removed: 1,
+                expected: concat!(
+                    "[dependencies]\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(any(windows,target_os = \"macos\"))'.dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.x86_64-pc-windows-gnu.build-dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+            },
+            RemoveTest {
+                dep_kind: DependencyKind::Normal,
+                targets: &[None, Some("cfg(unix)")],
+                key: "fuchsia",
+                removed: 2,
+                expected: concat!(
+                    "[dependencies]\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(any(windows,target_os = \"macos\"))'.dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.x86_64-pc-windows-gnu.build-dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+            },
+            RemoveTest {
+                dep_kind: DependencyKind::Normal,
+                // As formatted by `cargo metadata`.
+                targets: &[Some("cfg(any(windows, target_os = \"macos\"))")],
+                key: "purple",
+                removed: 1,
+                expected: concat!(
+                    "[dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(any(windows,target_os = \"macos\"))'.dependencies]\n",
+                    "\n",
+                    "[target.x86_64-pc-windows-gnu.build-dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+            },
+            RemoveTest {
+                dep_kind: DependencyKind::Build,
+                targets: &[Some("x86_64-pc-windows-gnu")],
+                key: "fuchsia",
+                removed: 1,
+                expected: concat!(
+                    "[dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(any(windows,target_os = \"macos\"))'.dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.x86_64-pc-windows-gnu.build-dependencies]\n",
+                ),
+            },
+            RemoveTest {
+                dep_kind: DependencyKind::Build,
+                targets: &[Some("cfg(unix)")],
+                key: "fuchsia",
+                removed: 0,
+                expected: manifest,
+            },
+        ];
+        for remove_test in remove_tests.iter() {
+            let targets: Vec<Option<String>> = remove_test
+                .targets
+                .iter()
+                .map(|target| {
+                    return target.map(str::to_owned);
+                })
+                .collect();
+            let mut document = toml_edit::Document::from_str(manifest).unwrap();
+            assert_eq!(
+                super::remove_dependency(
+                    &mut document,
+                    remove_test.dep_kind,
+                    targets.as_slice(),
+                    remove_test.key
+                ),
+                remove_test.removed
+            );
+            assert_eq!(document.to_string(), remove_test.expected);
+        }
+    }
+
+    #[test]
+    fn test_move_dependency() {
+        let manifest: &str = concat!(
+            "[package]\n",
+            "name = \"magenta\"\n",
+            "\n",
+            "[dependencies]\n",
+            "# Only for the tests.\n",
+            "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+            "fuchsia = \"1.0\"\n",
+            "purple = \"1.0\"\n",
+            "\n",
+            "[dev-dependencies]\n",
+            "purple = \"1.0\"\n",
+            "\n",
+            "[target.'cfg(unix)'.dependencies]\n",
+            "barley = \"1.0\"\n",
+            "purple = \"1.0\"\n",
+        );
+        struct MoveTest {
+            targets: &'static [Option<&'static str>],
+            key: &'static str,
+            dep_kind: DependencyKind,
+            moved: usize,
+            conflicts: usize,
+            expected: &'static str,
+        }
+        let move_tests: &[MoveTest] = &[
+            MoveTest {
+                targets: &[None],
+                key: "barley",
+                dep_kind: DependencyKind::Development,
+                moved: 1,
+                conflicts: 0,
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "# Only for the tests.\n",
+                    "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "barley = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                ),
+            },
+            MoveTest {
+                targets: &[Some("cfg(unix)")],
+                key: "barley",
+                dep_kind: DependencyKind::Development,
+                moved: 1,
+                conflicts: 0,
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "# Only for the tests.\n",
+                    "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dev-dependencies]\n",
+                    "barley = \"1.0\"\n",
+                ),
+            },
+            // Already a dev-dependency, so only removed.
+            MoveTest {
+                targets: &[None],
+                key: "purple",
+                dep_kind: DependencyKind::Development,
+                moved: 1,
+                conflicts: 0,
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "# Only for the tests.\n",
+                    "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+                    "fuchsia = \"1.0\"\n",
+                    "\n",
+                    "[dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "barley = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                ),
+            },
+            MoveTest {
+                targets: &[None],
+                key: "missing",
+                dep_kind: DependencyKind::Development,
+                moved: 0,
+                conflicts: 0,
+                expected: manifest,
+            },
+            MoveTest {
+                targets: &[Some("cfg(unix)")],
+                key: "purple",
+                dep_kind: DependencyKind::Development,
+                moved: 1,
+                conflicts: 0,
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "# Only for the tests.\n",
+                    "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+                    "fuchsia = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "barley = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                ),
+            },
+            // Build scripts can only use build-dependencies.
+            MoveTest {
+                targets: &[None],
+                key: "fuchsia",
+                dep_kind: DependencyKind::Build,
+                moved: 1,
+                conflicts: 0,
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "# Only for the tests.\n",
+                    "barley = { version = \"1.0\", features = [\"std\"] } # Grain.\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[dev-dependencies]\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[target.'cfg(unix)'.dependencies]\n",
+                    "barley = \"1.0\"\n",
+                    "purple = \"1.0\"\n",
+                    "\n",
+                    "[build-dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+            },
+        ];
+        for move_test in move_tests.iter() {
+            let targets: Vec<Option<String>> = move_test
+                .targets
+                .iter()
+                .map(|target| {
+                    return target.map(str::to_owned);
+                })
+                .collect();
+            let mut document = toml_edit::Document::from_str(manifest).unwrap();
+            assert_eq!(
+                super::move_dependency(
+                    &mut document,
+                    targets.as_slice(),
+                    move_test.key,
+                    move_test.dep_kind
+                )
+                .unwrap(),
+                super::MovedEntries {
+                    moved: move_test.moved,
+                    conflicts: move_test.conflicts,
+                },
+                "{} {:?}",
+                move_test.key,
+                move_test.targets
+            );
+            assert_eq!(
+                document.to_string(),
+                move_test.expected,
+                "{} {:?}",
+                move_test.key,
+                move_test.targets
+            );
+        }
+
+        // Already a dev-dependency, with a different, or an equivalent spec.
+        let manifest: &str = concat!(
+            "[dependencies]\n",
+            "barley = { version = \"1.0\", default-features = false }\n",
+            "purple = { version = \"1.0\" }\n",
+            "\n",
+            "[dev-dependencies]\n",
+            "barley = \"1.0\"\n",
+            "purple = \"1.0\"\n",
+        );
+        let mut document = toml_edit::Document::from_str(manifest).unwrap();
+        assert_eq!(
+            super::move_dependency(
+                &mut document,
+                &[None],
+                "barley",
+                DependencyKind::Development
+            )
+            .unwrap(),
+            super::MovedEntries {
+                moved: 0,
+                conflicts: 1,
+            }
+        );
+        assert_eq!(document.to_string(), manifest);
+        assert_eq!(
+            super::move_dependency(
+                &mut document,
+                &[None],
+                "purple",
+                DependencyKind::Development
+            )
+            .unwrap(),
+            super::MovedEntries {
+                moved: 1,
+                conflicts: 0,
+            }
+        );
+        assert_eq!(
+            document.to_string(),
+            concat!(
+                "[dependencies]\n",
+                "barley = { version = \"1.0\", default-features = false }\n",
+                "\n",
+                "[dev-dependencies]\n",
+                "barley = \"1.0\"\n",
+                "purple = \"1.0\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_remove_feature_references() {
+        struct FeatureTest {
+            manifest: &'static str,
+            key: &'static str,
+            removed: usize,
+            expected: &'static str,
+        }
+        let feature_tests: &[FeatureTest] = &[
+            FeatureTest {
+                manifest: concat!(
+                    "[features]\n",
+                    "default = [\"fuchsia\", \"std\"]\n",
+                    "std = [\"fuchsia/std\", \"purple/std\"]\n",
+                    "serde = [\"fuchsia?/serde\"]\n",
+                ),
+                key: "fuchsia",
+                removed: 3,
+                expected: concat!(
+                    "[features]\n",
+                    "default = [\"std\"]\n",
+                    "std = [\"purple/std\"]\n",
+                    "serde = []\n",
+                ),
+            },
+            FeatureTest {
+                manifest: concat!(
+                    "[features]\n",
+                    "default = [\"fuchsia\", \"purple\"]\n",
+                    "fuchsia = [\"dep:fuchsia\"]\n",
+                    "colours = [\n",
+                    "    \"dep:fuchsia\",\n",
+                    "    \"purple\",\n",
+                    "]\n",
+                ),
+                key: "fuchsia",
+                removed: 4,
+                expected: concat!(
+                    "[features]\n",
+                    "default = [\"purple\"]\n",
+                    "colours = [\n",
+                    "    \"purple\",\n",
+                    "]\n",
+                ),
+            },
+            FeatureTest {
+                // A feature named after the dependency which does more than
+                // enable it is kept, along with references to it.
+                manifest: concat!(
+                    "[features]\n",
+                    "default = [\"fuchsia\"]\n",
+                    "fuchsia = [\"dep:fuchsia\", \"purple\"]\n",
+                ),
+                key: "fuchsia",
+                removed: 1,
+                expected: concat!(
+                    "[features]\n",
+                    "default = [\"fuchsia\"]\n",
+                    "fuchsia = [\"purple\"]\n",
+                ),
+            },
+            FeatureTest {
+                manifest: concat!(
+                    "[dependencies]\n",
+                    "fuchsia-purple = \"1.0\"\n",
+                    "\n",
+                    "[features]\n",
+                    "std = [\"fuchsia-purple/std\"]\n",
+                ),
+                key: "fuchsia",
+                removed: 0,
+                expected: concat!(
+                    "[dependencies]\n",
+                    "fuchsia-purple = \"1.0\"\n",
+                    "\n",
+                    "[features]\n",
+                    "std = [\"fuchsia-purple/std\"]\n",
+                ),
+            },
+        ];
+        for feature_test in feature_tests.iter() {
+            let mut document = toml_edit::Document::from_str(feature_test.manifest).unwrap();
+            assert_eq!(
+                super::remove_feature_references(&mut document, feature_test.key),
+                feature_test.removed,
+                "{}",
+                feature_test.manifest
+            );
+            assert_eq!(document.to_string(), feature_test.expected);
+        }
+    }
+
+    #[test]
+    fn test_inherited_dependency_keys() {
+        let document = toml_edit::Document::from_str(concat!(
+            "[dependencies]\n",
+            "fuchsia.workspace = true\n",
+            "purple = { workspace = true, optional = true }\n",
+            "magenta = { workspace = false, path = \"../magenta\" }\n",
+            "\n",
+            "[dev-dependencies.lapin]\n",
+            "workspace = true\n",
+            "\n",
+            "[target.'cfg(unix)'.build-dependencies]\n",
+            "cochon = { workspace = true }\n",
+            "vache = \"1.0\"\n",
+        ))
+        .unwrap();
+        assert_eq!(
+            super::inherited_dependency_keys(&document),
+            BTreeSet::from([
+                "cochon".to_owned(),
+                "fuchsia".to_owned(),
+                "lapin".to_owned(),
+                "purple".to_owned(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_add_ignored_dependency() {
+        struct IgnoreTest {
+            manifest: &'static str,
+            expected: &'static str,
+        }
+        let ignore_tests: &[IgnoreTest] = &[
+            IgnoreTest {
+                manifest: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[package.metadata.reves]\n",
+                    "ignored = [\n",
+                    "  { name = \"fuchsia\", kind = \"normal\", reason = \"Forces a minimum version\" },\n",
+                    "]\n",
+                    "\n",
+                    "[dependencies]\n",
+                    "fuchsia = \"1.0\"\n",
+                ),
+            },
+            IgnoreTest {
+                manifest: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[package.metadata.reves]\n",
+                    "unused-dev = \"warn\"\n",
+                    "ignored = [\n",
+                    "  { name = \"purple\", reason = \"Used by a macro\" },\n",
+                    "]\n",
+                ),
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "\n",
+                    "[package.metadata.reves]\n",
+                    "unused-dev = \"warn\"\n",
+                    "ignored = [\n",
+                    "  { name = \"purple\", reason = \"Used by a macro\" },\n",
+                    "  { name = \"fuchsia\", kind = \"normal\", reason = \"Forces a minimum version\" },\n",
+                    "]\n",
+                ),
+            },
+            IgnoreTest {
+                manifest: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "metadata.reves.ignored = [{ name = \"purple\", reason = \"Used by a macro\" }]\n",
+                ),
+                expected: concat!(
+                    "[package]\n",
+                    "name = \"magenta\"\n",
+                    "metadata.reves.ignored = [{ name = \"purple\", reason = \"Used by a macro\" }, { name = \"fuchsia\", kind = \"normal\", reason = \"Forces a minimum version\" }]\n",
+                ),
+            },
+        ];
+        let ignored = IgnoredDependency {
+            name: "fuchsia".to_owned(),
+            kind: Some(DependencyKind::Normal),
+            reason: "Forces a minimum version".to_owned(),
+        };
+        for ignore_test in ignore_tests.iter() {
+            let mut document = toml_edit::Document::from_str(ignore_test.manifest).unwrap();
+            super::add_ignored_dependency(&mut document, &ignored).unwrap();
+            assert_eq!(document.to_string(), ignore_test.expected);
+        }
+    }
+
+    #[test]
+    fn test_find_unused_workspace_dependencies() {
+        let workspace_root: Utf8PathBuf = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../test_workspaces/workspace_unused");
+        let mut member_manifest_paths = BTreeSet::<Utf8PathBuf>::new();
+        for member in ["fuchsia", "magenta", "purple"].iter() {
+            member_manifest_paths.insert(workspace_root.join(member).join("Cargo.toml"));
+        }
+        let unused: Vec<UnusedWorkspaceDependency> = super::find_unused_workspace_dependencies(
+            &workspace_root.join("Cargo.toml"),
+            &member_manifest_paths,
+        )
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].key, "magenta");
+        assert_eq!(unused[0].manifest_span.unwrap().line_start, 13);
+    }
+}
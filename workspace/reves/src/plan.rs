@@ -0,0 +1,462 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::fix::ManifestFix;
+use crate::github::relative_path;
+use crate::manifest;
+
+/*
+  A plan splits `--fix` in two: `reves plan` records the edits it would make to
+  each manifest, and `reves apply` makes them later (possibly on another
+  checkout), without linting the workspace again. Each edit records the value
+  it expects to replace, so a manifest which has changed since the plan was
+  made is left untouched rather than edited blindly.
+*/
+
+/// Bumped whenever the meaning of an edit changes, older plans are then
+/// rejected rather than misapplied.
+const PLAN_VERSION: u32 = 1;
+
+/// The removal, insertion, or replacement of the value of `key` in a table of a
+/// manifest.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PlanEdit {
+    /// Relative to the workspace root.
+    pub(crate) manifest_path: Utf8PathBuf,
+    /// The keys of the (possibly nested) table containing `key`, such as
+    /// `["target", "cfg(unix)", "dependencies"]`.
+    pub(crate) table: Vec<String>,
+    pub(crate) key: String,
+    /// The TOML text of the value before the edit, or `None` to insert `key`
+    /// (creating its table if needed).
+    pub(crate) expected: Option<String>,
+    /// The TOML text of the value after the edit, or `None` to remove `key`.
+    pub(crate) replacement: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(deny_unknown_fields)]
+struct Plan {
+    version: u32,
+    edits: Vec<PlanEdit>,
+}
+
+/// The text of `item` without its surrounding whitespace, or comments, which
+/// belong to the key rather than the value.
+fn item_text(item: &toml_edit::Item) -> String {
+    let mut item: toml_edit::Item = item.clone();
+    if let Some(value) = item.as_value_mut() {
+        value.decor_mut().clear();
+    }
+    return item.to_string().trim().to_owned();
+}
+
+fn table_edits(
+    manifest_path: &Utf8Path,
+    table_path: &mut Vec<String>,
+    original: &toml_edit::Table,
+    fixed: &toml_edit::Table,
+    edits: &mut Vec<PlanEdit>,
+) {
+    for (key, original_item) in original.iter() {
+        let fixed_item: &toml_edit::Item = match fixed.get(key) {
+            Some(fixed_item) => fixed_item,
+            None => {
+                edits.push(PlanEdit {
+                    manifest_path: manifest_path.to_owned(),
+                    table: table_path.clone(),
+                    key: key.to_owned(),
+                    expected: Some(item_text(original_item)),
+                    replacement: None,
+                });
+                continue;
+            }
+        };
+        if let (Some(original_table), Some(fixed_table)) =
+            (original_item.as_table(), fixed_item.as_table())
+        {
+            table_path.push(key.to_owned());
+            table_edits(
+                manifest_path,
+                table_path,
+                original_table,
+                fixed_table,
+                edits,
+            );
+            table_path.pop();
+        } else if item_text(original_item) != item_text(fixed_item) {
+            edits.push(PlanEdit {
+                manifest_path: manifest_path.to_owned(),
+                table: table_path.clone(),
+                key: key.to_owned(),
+                expected: Some(item_text(original_item)),
+                replacement: Some(item_text(fixed_item)),
+            });
+        }
+    }
+    for (key, fixed_item) in fixed.iter() {
+        if original.contains_key(key) {
+            continue;
+        }
+        match fixed_item.as_table() {
+            // Inserted key by key, as a table (such as `[dev-dependencies]`)
+            // isn't a value.
+            Some(fixed_table) => {
+                table_path.push(key.to_owned());
+                table_edits(
+                    manifest_path,
+                    table_path,
+                    &toml_edit::Table::new(),
+                    fixed_table,
+                    edits,
+                );
+                table_path.pop();
+            }
+            None => edits.push(PlanEdit {
+                manifest_path: manifest_path.to_owned(),
+                table: table_path.clone(),
+                key: key.to_owned(),
+                expected: None,
+                replacement: Some(item_text(fixed_item)),
+            }),
+        }
+    }
+}
+
+/// The edits making `fixes`, with manifest paths relative to `workspace_root`.
+pub(crate) fn plan_edits(
+    workspace_root: &Utf8Path,
+    fixes: &BTreeMap<Utf8PathBuf, ManifestFix>,
+) -> anyhow::Result<Vec<PlanEdit>> {
+    let mut edits = Vec::<PlanEdit>::new();
+    for (manifest_path, fix) in fixes.iter() {
+        let original = toml_edit::Document::from_str(fix.original.as_str())
+            .with_context(|| format!("Failed to parse {}", manifest_path))?;
+        let fixed = toml_edit::Document::from_str(fix.fixed.as_str())
+            .with_context(|| format!("Failed to parse the fixed {}", manifest_path))?;
+        table_edits(
+            Utf8Path::new(&relative_path(workspace_root, manifest_path)),
+            &mut Vec::new(),
+            original.as_table(),
+            fixed.as_table(),
+            &mut edits,
+        );
+    }
+    return Ok(edits);
+}
+
+pub(crate) fn write_plan(path: &Path, edits: &[PlanEdit]) -> anyhow::Result<()> {
+    let mut plan_text: String = serde_json::to_string_pretty(&Plan {
+        version: PLAN_VERSION,
+        edits: edits.to_vec(),
+    })?;
+    plan_text.push('\n');
+    std::fs::write(path, plan_text)
+        .with_context(|| format!("Failed to write plan {}", path.display()))?;
+    return Ok(());
+}
+
+pub(crate) fn read_plan(path: &Path) -> anyhow::Result<Vec<PlanEdit>> {
+    let plan_text: String = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read plan {}", path.display()))?;
+    let plan: Plan = serde_json::from_str(&plan_text)
+        .with_context(|| format!("Invalid plan {}", path.display()))?;
+    anyhow::ensure!(
+        plan.version == PLAN_VERSION,
+        "Unsupported plan version {} in {}, expected {}",
+        plan.version,
+        path.display(),
+        PLAN_VERSION
+    );
+    return Ok(plan.edits);
+}
+
+fn parse_replacement(edit: &PlanEdit, replacement: &str) -> Result<toml_edit::Value, String> {
+    return toml_edit::Value::from_str(replacement).map_err(|e| {
+        return format!("Invalid replacement for `{}`: {}", edit.key, e);
+    });
+}
+
+/// Inserts the value of `edit`, which expects `key` to be missing.
+fn insert_edit(document: &mut toml_edit::Document, edit: &PlanEdit) -> Result<(), String> {
+    let table_path: Vec<&str> = edit.table.iter().map(String::as_str).collect();
+    let mut table: &mut toml_edit::Table = document.as_table_mut();
+    for table_name in table_path.iter() {
+        table = manifest::table_entry(table, table_name).map_err(|e| {
+            return e.to_string();
+        })?;
+    }
+    if table.contains_key(edit.key.as_str()) {
+        return Err(format!(
+            "`{}` is already in [{}]",
+            edit.key,
+            table_path.join(".")
+        ));
+    }
+    let replacement: &str = edit.replacement.as_deref().ok_or_else(|| {
+        return format!("Nothing to insert for `{}`", edit.key);
+    })?;
+    table.insert(
+        edit.key.as_str(),
+        toml_edit::value(parse_replacement(edit, replacement)?),
+    );
+    return Ok(());
+}
+
+/// Makes `edit` in `document`, or returns why it can't be made.
+fn apply_edit(document: &mut toml_edit::Document, edit: &PlanEdit) -> Result<(), String> {
+    let expected: &str = match edit.expected.as_deref() {
+        Some(expected) => expected,
+        None => return insert_edit(document, edit),
+    };
+    let table_path: Vec<&str> = edit.table.iter().map(String::as_str).collect();
+    let table: &mut dyn toml_edit::TableLike = manifest::table_like_mut(document, &table_path)
+        .ok_or_else(|| {
+            return format!("[{}] is missing", table_path.join("."));
+        })?;
+    let item: &mut toml_edit::Item = table.get_mut(edit.key.as_str()).ok_or_else(|| {
+        return format!("`{}` is missing from [{}]", edit.key, table_path.join("."));
+    })?;
+    if item_text(item) != expected {
+        return Err(format!(
+            "`{}` in [{}] has changed",
+            edit.key,
+            table_path.join(".")
+        ));
+    }
+    match edit.replacement.as_ref() {
+        None => {
+            table.remove(edit.key.as_str());
+        }
+        Some(replacement) => {
+            let mut value: toml_edit::Value = parse_replacement(edit, replacement)?;
+            let current_value: &mut toml_edit::Value = item.as_value_mut().ok_or_else(|| {
+                return format!("`{}` in [{}] isn't a value", edit.key, table_path.join("."));
+            })?;
+            *value.decor_mut() = current_value.decor().clone();
+            *current_value = value;
+        }
+    }
+    return Ok(());
+}
+
+/// The fixes making `edits` in the manifests under `workspace_root`, and the
+/// manifests which have changed since the plan was made (with the reason),
+/// none of whose edits are made.
+pub(crate) fn apply_plan(
+    workspace_root: &Utf8Path,
+    edits: &[PlanEdit],
+) -> anyhow::Result<(
+    BTreeMap<Utf8PathBuf, ManifestFix>,
+    BTreeMap<Utf8PathBuf, String>,
+)> {
+    let mut manifest_edits = BTreeMap::<&Utf8Path, Vec<&PlanEdit>>::new();
+    for edit in edits.iter() {
+        manifest_edits
+            .entry(edit.manifest_path.as_path())
+            .or_default()
+            .push(edit);
+    }
+
+    let mut fixes = BTreeMap::<Utf8PathBuf, ManifestFix>::new();
+    let mut refused = BTreeMap::<Utf8PathBuf, String>::new();
+    for (relative_manifest_path, edits) in manifest_edits.into_iter() {
+        let manifest_path: Utf8PathBuf = workspace_root.join(relative_manifest_path);
+        let (original, mut document) = manifest::read_manifest(&manifest_path)?;
+        let mut refusal: Option<String> = None;
+        for edit in edits.iter() {
+            if let Err(reason) = apply_edit(&mut document, edit) {
+                refusal = Some(reason);
+                break;
+            }
+        }
+        if let Some(reason) = refusal {
+            refused.insert(manifest_path, reason);
+            continue;
+        }
+        fixes.insert(
+            manifest_path,
+            ManifestFix {
+                original,
+                fixed: document.to_string(),
+            },
+        );
+    }
+    return Ok((fixes, refused));
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use camino::Utf8PathBuf;
+
+    use super::PlanEdit;
+    use crate::fix::ManifestFix;
+
+    #[test]
+    fn test_plan_edits() {
+        let original: &str = r#"[package]
+name = "magenta"
+
+[features]
+default = ["std", "dep:fuchsia"] # The usual.
+std = []
+
+[dependencies]
+fuchsia = { version = "1", optional = true } # Colours.
+purple = "1"
+
+[target.'cfg(unix)'.dependencies]
+fuchsia = "1"
+"#;
+        let fixed: &str = r#"[package]
+name = "magenta"
+
+[features]
+default = ["std"] # The usual.
+std = []
+
+[dependencies]
+purple = "1"
+
+[target.'cfg(unix)'.dependencies]
+"#;
+        let workspace_root = Utf8PathBuf::from("/ws");
+        let manifest_path: Utf8PathBuf = workspace_root.join("magenta/Cargo.toml");
+        let fixes = BTreeMap::from([(
+            manifest_path.clone(),
+            ManifestFix {
+                original: original.to_owned(),
+                fixed: fixed.to_owned(),
+            },
+        )]);
+
+        let edits: Vec<PlanEdit> = super::plan_edits(&workspace_root, &fixes).unwrap();
+        let expected_edits: Vec<PlanEdit> = vec![
+            PlanEdit {
+                manifest_path: Utf8PathBuf::from("magenta/Cargo.toml"),
+                table: vec!["features".to_owned()],
+                key: "default".to_owned(),
+                expected: Some(r#"["std", "dep:fuchsia"]"#.to_owned()),
+                replacement: Some(r#"["std"]"#.to_owned()),
+            },
+            PlanEdit {
+                manifest_path: Utf8PathBuf::from("magenta/Cargo.toml"),
+                table: vec!["dependencies".to_owned()],
+                key: "fuchsia".to_owned(),
+                expected: Some(r#"{ version = "1", optional = true }"#.to_owned()),
+                replacement: None,
+            },
+            PlanEdit {
+                manifest_path: Utf8PathBuf::from("magenta/Cargo.toml"),
+                table: vec![
+                    "target".to_owned(),
+                    "cfg(unix)".to_owned(),
+                    "dependencies".to_owned(),
+                ],
+                key: "fuchsia".to_owned(),
+                expected: Some(r#""1""#.to_owned()),
+                replacement: None,
+            },
+        ];
+        assert_eq!(edits, expected_edits);
+
+        let mut document = toml_edit::Document::from_str(original).unwrap();
+        for edit in edits.iter() {
+            super::apply_edit(&mut document, edit).unwrap();
+        }
+        assert_eq!(document.to_string(), fixed);
+
+        // A manifest changed since the plan was made.
+        let mut document = toml_edit::Document::from_str(
+            &original.replace("fuchsia = \"1\"\n", "fuchsia = \"2\"\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            super::apply_edit(&mut document, &edits[2]),
+            Err("`fuchsia` in [target.cfg(unix).dependencies] has changed".to_owned())
+        );
+        assert_eq!(super::apply_edit(&mut document, &edits[1]), Ok(()));
+        assert_eq!(
+            super::apply_edit(&mut document, &edits[1]),
+            Err("`fuchsia` is missing from [dependencies]".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_plan_insertions() {
+        let original: &str = r#"[package]
+name = "magenta"
+
+[dependencies]
+barley = "1"
+fuchsia = "1"
+
+[target.'cfg(unix)'.dependencies]
+purple = { version = "1", features = ["std"] }
+"#;
+        let fixed: &str = r#"[package]
+name = "magenta"
+
+[dependencies]
+fuchsia = "1"
+
+[target.'cfg(unix)'.dependencies]
+
+[target.'cfg(unix)'.dev-dependencies]
+purple = { version = "1", features = ["std"] }
+
+[dev-dependencies]
+barley = "1"
+"#;
+        let workspace_root = Utf8PathBuf::from("/ws");
+        let fixes = BTreeMap::from([(
+            workspace_root.join("magenta/Cargo.toml"),
+            ManifestFix {
+                original: original.to_owned(),
+                fixed: fixed.to_owned(),
+            },
+        )]);
+
+        let edits: Vec<PlanEdit> = super::plan_edits(&workspace_root, &fixes).unwrap();
+        let mut insertions = Vec::<(Vec<&str>, &str, Option<&str>)>::new();
+        for edit in edits.iter() {
+            if edit.expected.is_none() {
+                insertions.push((
+                    edit.table.iter().map(String::as_str).collect(),
+                    edit.key.as_str(),
+                    edit.replacement.as_deref(),
+                ));
+            }
+        }
+        assert_eq!(
+            insertions,
+            [
+                (
+                    vec!["target", "cfg(unix)", "dev-dependencies"],
+                    "purple",
+                    Some(r#"{ version = "1", features = ["std"] }"#)
+                ),
+                (vec!["dev-dependencies"], "barley", Some(r#""1""#)),
+            ]
+        );
+
+        let mut document = toml_edit::Document::from_str(original).unwrap();
+        for edit in edits.iter() {
+            super::apply_edit(&mut document, edit).unwrap();
+        }
+        assert_eq!(document.to_string(), fixed);
+        assert_eq!(
+            super::apply_edit(&mut document, &edits[edits.len() - 1]),
+            Err("`barley` is already in [dev-dependencies]".to_owned())
+        );
+    }
+}
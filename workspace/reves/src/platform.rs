@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::Context;
+
+use crate::CheckTarget;
+
+/// The platform being checked, used to tell which
+/// `[target.<platform>.*dependencies]` tables are active.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TargetPlatform {
+    triple: String,
+    cfgs: Vec<cargo_platform::Cfg>,
+}
+
+fn rustc_command() -> Cow<'static, OsStr> {
+    return match std::env::var_os("RUSTC") {
+        Some(rustc_command) => Cow::Owned(rustc_command),
+        None => Cow::Borrowed(OsStr::new("rustc")),
+    };
+}
+
+fn rustc_output(workspace: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output: std::process::Output = Command::new(rustc_command())
+        .current_dir(workspace)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()?;
+    anyhow::ensure!(output.status.success(), "rustc {:?} failed", args);
+    return Ok(String::from_utf8(output.stdout)?);
+}
+
+fn parse_host_triple(version_output: &str) -> anyhow::Result<String> {
+    for line in version_output.lines() {
+        if let Some(host) = line.strip_prefix("host:") {
+            return Ok(host.trim().to_owned());
+        }
+    }
+    anyhow::bail!("unable to find the rustc host");
+}
+
+impl TargetPlatform {
+    /// `cfg_output` is the output of `rustc --print cfg`.
+    fn from_cfg_output(triple: String, cfg_output: &str) -> anyhow::Result<TargetPlatform> {
+        let mut cfgs = Vec::<cargo_platform::Cfg>::new();
+        for line in cfg_output.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            cfgs.push(
+                cargo_platform::Cfg::from_str(line)
+                    .with_context(|| format!("Invalid rustc cfg {}", line))?,
+            );
+        }
+        return Ok(TargetPlatform { triple, cfgs });
+    }
+
+    pub(crate) fn query(
+        workspace: &Path,
+        check_target: &CheckTarget,
+    ) -> anyhow::Result<TargetPlatform> {
+        return match check_target {
+            CheckTarget::Host => TargetPlatform::from_cfg_output(
+                parse_host_triple(&rustc_output(workspace, &["-vV"])?)?,
+                &rustc_output(workspace, &["--print", "cfg"])?,
+            ),
+            CheckTarget::Target(target) => TargetPlatform::from_cfg_output(
+                target.clone(),
+                &rustc_output(workspace, &["--print", "cfg", "--target", target.as_str()])?,
+            ),
+        };
+    }
+
+    /// Whether a dependency declared for `platform` (such as the `target` of
+    /// a `cargo_metadata::Dependency`) applies to this platform.
+    pub(crate) fn matches(&self, platform: Option<&cargo_platform::Platform>) -> bool {
+        return match platform {
+            Some(platform) => platform.matches(self.triple.as_str(), self.cfgs.as_slice()),
+            None => true,
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::TargetPlatform;
+
+    #[test]
+    fn test_parse_host_triple() {
+        assert_eq!(
+            super::parse_host_triple(concat!(
+                "rustc 1.72.1 (d5c2e9c34 2023-09-13)\n",
+                "binary: rustc\n",
+                "commit-hash: d5c2e9c342b358556da91d61ed4133f6f50fc0c3\n",
+                "commit-date: 2023-09-13\n",
+                "host: x86_64-unknown-linux-gnu\n",
+                "release: 1.72.1\n",
+                "LLVM version: 16.0.5\n",
+            ))
+            .unwrap(),
+            "x86_64-unknown-linux-gnu"
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let platform = TargetPlatform::from_cfg_output(
+            "x86_64-unknown-linux-gnu".to_owned(),
+            concat!(
+                "debug_assertions\n",
+                "panic=\"unwind\"\n",
+                "target_arch=\"x86_64\"\n",
+                "target_family=\"unix\"\n",
+                "target_os=\"linux\"\n",
+                "unix\n",
+            ),
+        )
+        .unwrap();
+        let platform_tests: &[(&str, bool)] = &[
+            ("cfg(unix)", true),
+            ("cfg(windows)", false),
+            ("cfg(all(unix, target_arch = \"x86_64\"))", true),
+            ("cfg(not(target_os = \"linux\"))", false),
+            ("x86_64-unknown-linux-gnu", true),
+            ("x86_64-pc-windows-gnu", false),
+        ];
+        for (target, expected) in platform_tests.iter() {
+            assert_eq!(
+                platform.matches(Some(&cargo_platform::Platform::from_str(target).unwrap())),
+                *expected,
+                "{}",
+                target
+            );
+        }
+        assert!(platform.matches(None));
+    }
+}
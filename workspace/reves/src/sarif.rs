@@ -0,0 +1,595 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use camino::Utf8Path;
+
+use crate::github::mislabeled_dependency_text;
+use crate::github::optional_candidate_text;
+use crate::github::redundant_dev_dependency_text;
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::ManifestSpan;
+use crate::OptionalCandidate;
+use crate::OrphanArtifact;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+const SOURCE_ROOT: &str = "SRCROOT";
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run {
+    tool: Tool,
+    original_uri_base_ids: BTreeMap<&'static str, ArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    rule_index: usize,
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+impl From<ManifestSpan> for Region {
+    fn from(span: ManifestSpan) -> Region {
+        return Region {
+            start_line: span.line_start,
+            start_column: span.column_start,
+            end_line: span.line_end,
+            end_column: span.column_end,
+        };
+    }
+}
+
+// The order of `RULES` defines the `ruleIndex` of each rule.
+const RULES: &[(&str, &str)] = &[
+    (
+        "unused-normal",
+        "A [dependencies] entry is not used by any artifact of the package",
+    ),
+    (
+        "unused-dev",
+        "A [dev-dependencies] entry is not used by any test, bench, or example",
+    ),
+    (
+        "unused-build",
+        "A [build-dependencies] entry is not used by the build script",
+    ),
+    (
+        "orphan-artifact",
+        "An artifact doesn't use the library of its own package",
+    ),
+    (
+        "unused-workspace",
+        "A [workspace.dependencies] entry is not inherited by any workspace member",
+    ),
+    (
+        "mislabeled",
+        "A [dependencies] entry is only used by tests, benches, or examples, or by the build script, and should be a [dev-dependencies], or [build-dependencies] entry",
+    ),
+    (
+        "redundant",
+        "A [dev-dependencies] entry is subsumed by a [dependencies] entry, which tests can already use",
+    ),
+    (
+        "optional",
+        "A [dependencies] entry is only used when a feature is enabled, and could be an optional dependency enabled by that feature",
+    ),
+];
+
+fn rule_index(rule_id: &str) -> usize {
+    return RULES
+        .iter()
+        .position(|(id, _)| {
+            return *id == rule_id;
+        })
+        .unwrap();
+}
+
+fn artifact_location(workspace_root: &Utf8Path, path: &Utf8Path) -> ArtifactLocation {
+    return match path.strip_prefix(workspace_root) {
+        Ok(relative_path) => ArtifactLocation {
+            uri: relative_path.as_str().replace('\\', "/"),
+            uri_base_id: Some(SOURCE_ROOT),
+        },
+        Err(_) => ArtifactLocation {
+            uri: path.as_str().replace('\\', "/"),
+            uri_base_id: None,
+        },
+    };
+}
+
+fn unused_dependency_result(
+    results: &DependencyLintResults,
+    unused_dep: &UnusedDependency,
+) -> SarifResult {
+    let rule_id: &'static str = unused_dep.dep_kind.unused_finding_id();
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results.unused_dependency_level(unused_dep).report_level(),
+        message: Message {
+            text: format!(
+                "`{}` is an unused {} of `{}`",
+                unused_dep.dependency_name.name,
+                unused_dep.dep_kind,
+                results.package_name(&unused_dep.dependant),
+            ),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(
+                    &results.workspace_root,
+                    &unused_dep.dependant_manifest_path,
+                ),
+                region: unused_dep.manifest_span.map(Region::from),
+            },
+        }],
+    };
+}
+
+fn orphan_result(results: &DependencyLintResults, orphan: &OrphanArtifact) -> SarifResult {
+    let rule_id: &'static str = "orphan-artifact";
+    let mut locations = Vec::<Location>::new();
+    if let Some(artifact_path) = results.orphan_path(orphan) {
+        locations.push(Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(&results.workspace_root, &artifact_path),
+                region: None,
+            },
+        });
+    }
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results.orphan_level(orphan).report_level(),
+        message: Message {
+            text: format!(
+                "`{}` doesn't use the library of `{}`",
+                orphan.artifact_name,
+                results.package_name(&orphan.crate_id),
+            ),
+        },
+        locations,
+    };
+}
+
+fn unused_workspace_dependency_result(
+    results: &DependencyLintResults,
+    unused_workspace_dep: &UnusedWorkspaceDependency,
+) -> SarifResult {
+    let rule_id: &'static str = "unused-workspace";
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results.unused_workspace_dependency_level().report_level(),
+        message: Message {
+            text: format!(
+                "`{}` is an unused workspace dependency",
+                unused_workspace_dep.key
+            ),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(
+                    &results.workspace_root,
+                    &unused_workspace_dep.manifest_path,
+                ),
+                region: unused_workspace_dep.manifest_span.map(Region::from),
+            },
+        }],
+    };
+}
+
+/// `dep_kind` is the kind of dependency `mismarked_dep` should be.
+fn mislabeled_dependency_result(
+    results: &DependencyLintResults,
+    mismarked_dep: &UnusedDependency,
+    dep_kind: DependencyKind,
+) -> SarifResult {
+    let rule_id: &'static str = "mislabeled";
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results
+            .mislabeled_dependency_level(mismarked_dep)
+            .report_level(),
+        message: Message {
+            text: mislabeled_dependency_text(results, mismarked_dep, dep_kind),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(
+                    &results.workspace_root,
+                    &mismarked_dep.dependant_manifest_path,
+                ),
+                region: mismarked_dep.manifest_span.map(Region::from),
+            },
+        }],
+    };
+}
+
+fn redundant_dev_dependency_result(
+    results: &DependencyLintResults,
+    redundant_dep: &UnusedDependency,
+) -> SarifResult {
+    let rule_id: &'static str = "redundant";
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results
+            .redundant_dev_dependency_level(redundant_dep)
+            .report_level(),
+        message: Message {
+            text: redundant_dev_dependency_text(results, redundant_dep),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(
+                    &results.workspace_root,
+                    &redundant_dep.dependant_manifest_path,
+                ),
+                region: redundant_dep.manifest_span.map(Region::from),
+            },
+        }],
+    };
+}
+
+fn optional_candidate_result(
+    results: &DependencyLintResults,
+    optional_candidate: &OptionalCandidate,
+) -> SarifResult {
+    let rule_id: &'static str = "optional";
+    return SarifResult {
+        rule_id,
+        rule_index: rule_index(rule_id),
+        level: results
+            .optional_candidate_level(optional_candidate)
+            .report_level(),
+        message: Message {
+            text: optional_candidate_text(results, optional_candidate),
+        },
+        locations: vec![Location {
+            physical_location: PhysicalLocation {
+                artifact_location: artifact_location(
+                    &results.workspace_root,
+                    &optional_candidate.unused_dependency.dependant_manifest_path,
+                ),
+                region: optional_candidate
+                    .unused_dependency
+                    .manifest_span
+                    .map(Region::from),
+            },
+        }],
+    };
+}
+
+fn sarif_log(results: &DependencyLintResults) -> SarifLog {
+    let mut unused_deps: Vec<&UnusedDependency> = results.unused_dependencies.iter().collect();
+    unused_deps.sort();
+    let mut orphans: Vec<&OrphanArtifact> = results.orphans.iter().collect();
+    orphans.sort();
+    let mut unused_workspace_deps: Vec<&UnusedWorkspaceDependency> =
+        results.unused_workspace_dependencies.iter().collect();
+    unused_workspace_deps.sort();
+    let mismarked_deps: Vec<(&UnusedDependency, DependencyKind)> =
+        results.mislabeled_dependencies();
+    let mut redundant_deps: Vec<&UnusedDependency> =
+        results.redundant_dev_dependencies.iter().collect();
+    redundant_deps.sort();
+    let mut optional_candidates: Vec<&OptionalCandidate> =
+        results.optional_candidates.iter().collect();
+    optional_candidates.sort();
+
+    let mut sarif_results = Vec::<SarifResult>::new();
+    for unused_dep in unused_deps.into_iter() {
+        sarif_results.push(unused_dependency_result(results, unused_dep));
+    }
+    for orphan in orphans.into_iter() {
+        sarif_results.push(orphan_result(results, orphan));
+    }
+    for unused_workspace_dep in unused_workspace_deps.into_iter() {
+        sarif_results.push(unused_workspace_dependency_result(
+            results,
+            unused_workspace_dep,
+        ));
+    }
+    for (mismarked_dep, dep_kind) in mismarked_deps.into_iter() {
+        sarif_results.push(mislabeled_dependency_result(
+            results,
+            mismarked_dep,
+            dep_kind,
+        ));
+    }
+    for redundant_dep in redundant_deps.into_iter() {
+        sarif_results.push(redundant_dev_dependency_result(results, redundant_dep));
+    }
+    for optional_candidate in optional_candidates.into_iter() {
+        sarif_results.push(optional_candidate_result(results, optional_candidate));
+    }
+
+    let mut rules = Vec::<Rule>::with_capacity(RULES.len());
+    for (id, description) in RULES.iter() {
+        rules.push(Rule {
+            id,
+            short_description: Message {
+                text: (*description).to_owned(),
+            },
+        });
+    }
+
+    return SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules,
+                },
+            },
+            original_uri_base_ids: BTreeMap::from([(
+                SOURCE_ROOT,
+                ArtifactLocation {
+                    uri: format!("file://{}/", results.workspace_root),
+                    uri_base_id: None,
+                },
+            )]),
+            results: sarif_results,
+        }],
+    };
+}
+
+pub(crate) fn write_sarif_report(
+    results: &DependencyLintResults,
+    writer: &mut dyn Write,
+) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(&mut *writer, &sarif_log(results))?;
+    writeln!(writer)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util;
+
+    #[test]
+    fn test_sarif_log() {
+        let log = super::sarif_log(&test_util::sample_results());
+        let value: serde_json::Value = serde_json::to_value(&log).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        let run: &serde_json::Value = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "reves");
+        assert_eq!(run["tool"]["driver"]["rules"][2]["id"], "unused-build");
+        assert_eq!(run["tool"]["driver"]["rules"][7]["id"], "optional");
+        assert_eq!(run["originalUriBaseIds"]["SRCROOT"]["uri"], "file:///ws/");
+        assert_eq!(
+            run["results"],
+            serde_json::json!([
+                {
+                    "ruleId": "unused-normal",
+                    "ruleIndex": 0,
+                    "level": "error",
+                    "message": {
+                        "text": "`fuchsia` is an unused dependency of `magenta`",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 6,
+                                "startColumn": 1,
+                                "endLine": 6,
+                                "endColumn": 8,
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "unused-build",
+                    "ruleIndex": 2,
+                    "level": "error",
+                    "message": {
+                        "text": "`fuchsia` is an unused build-dependency of `magenta`",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 12,
+                                "startColumn": 1,
+                                "endLine": 12,
+                                "endColumn": 8,
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "unused-dev",
+                    "ruleIndex": 1,
+                    "level": "warning",
+                    "message": {
+                        "text": "`purple` is an unused dev-dependency of `magenta`",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 9,
+                                "startColumn": 1,
+                                "endLine": 9,
+                                "endColumn": 7,
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "orphan-artifact",
+                    "ruleIndex": 3,
+                    "level": "error",
+                    "message": {
+                        "text": "`orphan_bin` doesn't use the library of `magenta`",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/src/bin/orphan_bin.rs",
+                                "uriBaseId": "SRCROOT",
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "unused-workspace",
+                    "ruleIndex": 4,
+                    "level": "error",
+                    "message": {
+                        "text": "`lapin` is an unused workspace dependency",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 6,
+                                "startColumn": 1,
+                                "endLine": 6,
+                                "endColumn": 6,
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "mislabeled",
+                    "ruleIndex": 5,
+                    "level": "warning",
+                    "message": {
+                        "text": "`barley` is only used by the tests of `magenta`, and should be a dev-dependency",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 15,
+                                "startColumn": 1,
+                                "endLine": 15,
+                                "endColumn": 7,
+                            },
+                        },
+                    }],
+                },
+                {
+                    "ruleId": "optional",
+                    "ruleIndex": 7,
+                    "level": "warning",
+                    "message": {
+                        "text": "`violet` is only used by `magenta` with the feature `std`, and could be an optional dependency enabled by it",
+                    },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": {
+                                "uri": "magenta/Cargo.toml",
+                                "uriBaseId": "SRCROOT",
+                            },
+                            "region": {
+                                "startLine": 16,
+                                "startColumn": 1,
+                                "endLine": 16,
+                                "endColumn": 7,
+                            },
+                        },
+                    }],
+                },
+            ])
+        );
+    }
+}
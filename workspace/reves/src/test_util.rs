@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+
+use camino::Utf8PathBuf;
+
+use crate::DependencyKind;
+use crate::DependencyLintResults;
+use crate::Level;
+use crate::Levels;
+use crate::OptionalCandidate;
+use crate::OrphanArtifact;
+use crate::OrphanArtifactKind;
+use crate::UnrenamedCrateOwned;
+use crate::UnusedDependency;
+use crate::UnusedWorkspaceDependency;
+use crate::WorkspaceMember;
+
+pub(crate) const MAGENTA_MANIFEST: &str = concat!(
+    "[package]\n",
+    "name = \"magenta\"\n",
+    "version = \"1.0.0\"\n",
+    "\n",
+    "[dependencies]\n",
+    "fuchsia = { path = \"../fuchsia\" }\n",
+    "\n",
+    "[dev-dependencies]\n",
+    "purple = { path = \"../purple\" }\n",
+    "\n",
+    "[build-dependencies]\n",
+    "fuchsia = { path = \"../fuchsia\" }\n",
+    "\n",
+    "[target.'cfg(unix)'.dependencies]\n",
+    "barley = \"1.0\"\n",
+    "violet = \"1.0\"\n",
+);
+
+pub(crate) const WORKSPACE_MANIFEST: &str = concat!(
+    "[workspace]\n",
+    "members = [\"magenta\", \"purple\"]\n",
+    "\n",
+    "[workspace.dependencies]\n",
+    "fuchsia = { path = \"fuchsia\" }\n",
+    "lapin = \"1.0\"\n",
+);
+
+pub(crate) fn package_id(repr: &str) -> cargo_metadata::PackageId {
+    return cargo_metadata::PackageId {
+        repr: repr.to_owned(),
+    };
+}
+
+pub(crate) fn unused_dependency(
+    dependant: &str,
+    dependency: &str,
+    dep_kind: DependencyKind,
+) -> UnusedDependency {
+    return UnusedDependency {
+        dependant: package_id(dependant),
+        dependency: package_id(dependency),
+        dep_kind,
+
+        dependency_name: UnrenamedCrateOwned {
+            name: Cow::Owned(dependency.to_owned()),
+        },
+        manifest_key: dependency.to_owned(),
+        dependant_manifest_path: Utf8PathBuf::from(format!("/ws/{}/Cargo.toml", dependant)),
+        targets: vec![None],
+        manifest_span: if dependant == "magenta" {
+            crate::manifest::find_dependency_span(MAGENTA_MANIFEST, dep_kind, &[None], dependency)
+                .unwrap()
+        } else {
+            None
+        },
+    };
+}
+
+/// A `[target.'cfg(unix)'.dependencies]` entry.
+fn unix_dependency(dependant: &str, dependency: &str) -> UnusedDependency {
+    let mut unix_dep: UnusedDependency =
+        unused_dependency(dependant, dependency, DependencyKind::Normal);
+    unix_dep.targets = vec![Some("cfg(unix)".to_owned())];
+    unix_dep.manifest_span = crate::manifest::find_dependency_span(
+        MAGENTA_MANIFEST,
+        DependencyKind::Normal,
+        unix_dep.targets.as_slice(),
+        dependency,
+    )
+    .unwrap();
+    return unix_dep;
+}
+
+/// A `[target.'cfg(unix)'.dependencies]` entry only used by tests.
+pub(crate) fn mismarked_dev_dependency(dependant: &str, dependency: &str) -> UnusedDependency {
+    return unix_dependency(dependant, dependency);
+}
+
+/// Results resembling the `simple_unused` test workspace, with an additional
+/// clean `purple` member, an orphaned binary, an unused workspace dependency,
+/// a dependency only used by tests, and a dependency only used with the `std`
+/// feature. `magenta` only warns about unused dev-dependencies.
+pub(crate) fn sample_results() -> DependencyLintResults {
+    return DependencyLintResults {
+        workspace_root: Utf8PathBuf::from("/ws"),
+        workspace_members: BTreeMap::from([
+            (
+                package_id("magenta"),
+                WorkspaceMember {
+                    name: "magenta".to_owned(),
+                    manifest_path: Utf8PathBuf::from("/ws/magenta/Cargo.toml"),
+                    levels: Levels {
+                        unused_dev: Level::Warn,
+                        ..Levels::default()
+                    },
+                    ignored: Vec::new(),
+                },
+            ),
+            (
+                package_id("purple"),
+                WorkspaceMember {
+                    name: "purple".to_owned(),
+                    manifest_path: Utf8PathBuf::from("/ws/purple/Cargo.toml"),
+                    levels: Levels::default(),
+                    ignored: Vec::new(),
+                },
+            ),
+        ]),
+        unused_dependencies: HashSet::from([
+            unused_dependency("magenta", "purple", DependencyKind::Development),
+            unused_dependency("magenta", "fuchsia", DependencyKind::Build),
+            unused_dependency("magenta", "fuchsia", DependencyKind::Normal),
+        ]),
+        suppressed_dependencies: HashSet::new(),
+        mismarked_dev_dependencies: HashSet::from([mismarked_dev_dependency("magenta", "barley")]),
+        mismarked_build_dependencies: HashSet::new(),
+        redundant_dev_dependencies: HashSet::new(),
+        optional_candidates: HashSet::from([OptionalCandidate {
+            unused_dependency: unix_dependency("magenta", "violet"),
+            feature: "std".to_owned(),
+        }]),
+        orphans: HashSet::from([OrphanArtifact {
+            crate_id: package_id("magenta"),
+            kind: OrphanArtifactKind::Binary,
+            artifact_name: "orphan_bin".to_owned(),
+            crate_relative_path: Utf8PathBuf::from("src/bin/orphan_bin.rs"),
+        }]),
+        unused_workspace_dependencies: HashSet::from([UnusedWorkspaceDependency {
+            key: "lapin".to_owned(),
+            manifest_path: Utf8PathBuf::from("/ws/Cargo.toml"),
+            manifest_span: crate::manifest::find_workspace_dependency_span(
+                WORKSPACE_MANIFEST,
+                "lapin",
+            )
+            .unwrap(),
+        }]),
+        workspace_levels: Levels::default(),
+        member_manifest_paths: BTreeSet::from([
+            Utf8PathBuf::from("/ws/magenta/Cargo.toml"),
+            Utf8PathBuf::from("/ws/purple/Cargo.toml"),
+        ]),
+        checked_artifacts: BTreeMap::from([
+            (
+                (package_id("magenta"), DependencyKind::Normal),
+                BTreeSet::from([
+                    "bin `magenta`".to_owned(),
+                    "bin `magenta` (test)".to_owned(),
+                    "bin `orphan_bin`".to_owned(),
+                ]),
+            ),
+            (
+                (package_id("magenta"), DependencyKind::Development),
+                BTreeSet::from(["bin `magenta` (test)".to_owned()]),
+            ),
+            (
+                (package_id("magenta"), DependencyKind::Build),
+                BTreeSet::from(["custom-build `build-script-build`".to_owned()]),
+            ),
+        ]),
+        feature_combinations: Vec::new(),
+        fixed_baseline_entries: Vec::new(),
+    };
+}
+
+/// A copy of `test_workspaces/<name>`'s manifests in a temporary directory
+/// (distinct for each `test`), for tests writing fixes.
+pub(crate) fn copy_test_workspace(name: &str, members: &[&str], test: &str) -> Utf8PathBuf {
+    let source: Utf8PathBuf = Utf8PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../test_workspaces")
+        .join(name);
+    let destination: Utf8PathBuf = Utf8PathBuf::try_from(std::env::temp_dir())
+        .unwrap()
+        .join(format!("reves-{}-{}-{}", name, test, std::process::id()));
+    for member in [""].iter().chain(members.iter()) {
+        std::fs::create_dir_all(destination.join(member)).unwrap();
+        std::fs::copy(
+            source.join(member).join("Cargo.toml"),
+            destination.join(member).join("Cargo.toml"),
+        )
+        .unwrap();
+    }
+    return destination;
+}